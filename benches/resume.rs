@@ -0,0 +1,95 @@
+//! Benchmark of resuming a run against a large existing output, comparing the resume state
+//! file of `--resume-state` to the full read of the output by `--dedup`.
+//!
+//! Run with `cargo bench --bench resume`. Peak memory is measured on Linux only.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use common::Fixture;
+use gitex::ConfigBuilder;
+
+/// Number of commits of the benchmarked repository, all saved by the first run.
+const NB_COMMITS: usize = 10_000;
+
+/// Reset the peak resident set size of the process, returning false if unsupported.
+fn reset_peak_memory() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Get a given memory field of the process status in KB, such as `VmRSS` or `VmHWM`.
+fn memory_kb(field: &str) -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// Run gitex on a given repository with the given options, printing its time and the growth of
+/// the resident set size it peaked at.
+fn bench(name: &str, fixture: &Fixture, output: &Path, options: &[(&str, &Path)], flags: &[&str]) {
+    let mut config = ConfigBuilder::new(fixture.path())
+        .extensions(&["rs"])
+        .size(NB_COMMITS)
+        .option("output", output);
+    for (option, value) in options {
+        config = config.option(option, value);
+    }
+    for flag in flags {
+        config = config.flag(flag);
+    }
+    let config = config.build().expect("invalid configuration");
+
+    let peak_memory = reset_peak_memory();
+    let rss_kb = memory_kb("VmRSS");
+    let start = Instant::now();
+    gitex::run(config).expect("failed to run gitex");
+    let elapsed = start.elapsed();
+    let peak_kb = memory_kb("VmHWM");
+
+    match (peak_memory, rss_kb, peak_kb) {
+        (true, Some(rss_kb), Some(peak_kb)) => eprintln!(
+            "{name}: {elapsed:.2?}, peak memory +{} KB",
+            peak_kb.saturating_sub(rss_kb)
+        ),
+        _ => eprintln!("{name}: {elapsed:.2?}"),
+    }
+}
+
+fn main() {
+    let fixture = Fixture::new();
+    for i in 0..NB_COMMITS {
+        let content = format!("// Change {i}\nfn change_{i}() {{\n    println!(\"{i}\");\n}}\n");
+        fixture.commit(
+            &format!("Add change {i}"),
+            &[(&format!("src/change_{}.rs", i % 100), content.as_bytes())],
+        );
+    }
+    let output = fixture.path().join("output.csv");
+    let state = fixture.path().join("output.state");
+    bench(
+        "first run",
+        &fixture,
+        &output,
+        &[("resume-state", &state)],
+        &[],
+    );
+
+    let output_size = fs::metadata(&output).expect("missing output").len();
+    let state_size = fs::metadata(&state).expect("missing resume state").len();
+    eprintln!("output: {output_size} bytes, resume state: {state_size} bytes");
+
+    bench(
+        "resume state",
+        &fixture,
+        &output,
+        &[("resume-state", &state)],
+        &[],
+    );
+    bench("dedup", &fixture, &output, &[], &["dedup"]);
+}
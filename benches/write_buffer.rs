@@ -0,0 +1,57 @@
+//! Benchmark of a large run writing its records with write buffers of different sizes.
+//!
+//! Run with `cargo bench --bench write_buffer`.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use common::Fixture;
+use gitex::ConfigBuilder;
+
+/// Number of commits of the benchmarked repository, all saved by each run.
+const NB_COMMITS: usize = 5_000;
+
+/// Number of runs of each write buffer size, the fastest being kept.
+const NB_RUNS: usize = 3;
+
+/// Write buffer sizes benchmarked, from below the standard library default of 8KB to above the
+/// gitex default of 64KB.
+const WRITE_BUFFER_SIZES: &[&str] = &["1KB", "8KB", "64KB", "1MB"];
+
+fn main() {
+    // Commit large changes, so that the run spends a fair part of its time writing records
+    let fixture = Fixture::new();
+    for i in 0..NB_COMMITS {
+        let content: String = (0..100)
+            .map(|line| format!("    let value_{line} = compute({i}, {line});\n"))
+            .collect();
+        fixture.commit(
+            &format!("Add change {i}"),
+            &[(&format!("src/change_{}.rs", i % 100), content.as_bytes())],
+        );
+    }
+    let output = fixture.path().join("output.csv");
+
+    for write_buffer_size in WRITE_BUFFER_SIZES {
+        let mut fastest = Duration::MAX;
+        for _ in 0..NB_RUNS {
+            let _ = fs::remove_file(&output);
+            let config = ConfigBuilder::new(fixture.path())
+                .extensions(&["rs"])
+                .size(NB_COMMITS)
+                .option("output", &output)
+                .option("write-buffer-size", write_buffer_size)
+                .option("changes-len-max", "100000")
+                .build()
+                .expect("invalid configuration");
+            let start = Instant::now();
+            gitex::run(config).expect("failed to run gitex");
+            fastest = fastest.min(start.elapsed());
+        }
+        let output_size = fs::metadata(&output).expect("missing output").len();
+        eprintln!("{write_buffer_size}: {fastest:.2?} to write {output_size} bytes");
+    }
+}
@@ -0,0 +1,4848 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use base64::prelude::*;
+use fs2::FileExt;
+use git2::{
+    Commit, Delta, Diff, DiffFindOptions, DiffOptions, ObjectType, Oid, Repository, RevparseMode,
+    Revwalk, Sort, Time, Tree, TreeWalkMode, TreeWalkResult,
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use log::info;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use regex::{Captures, Regex, RegexBuilder};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use structopt::StructOpt;
+
+/// Configuration of the extraction, parsed from the command line or built with `ConfigBuilder`.
+#[derive(StructOpt, Debug, Clone)]
+#[structopt(about = "Extract data from a Git repository.")]
+pub struct Config {
+    #[structopt(
+        long = "config",
+        parse(from_os_str),
+        help = "Path to a TOML file of options, those of the command line taking precedence"
+    )]
+    config_file: Option<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        required_unless = "repos-file",
+        conflicts_with = "repos-file",
+        help = "Path to the Git repository"
+    )]
+    repository: Option<PathBuf>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "File listing Git repositories to extract from into the same output, one `path[,label]` per line"
+    )]
+    repos_file: Option<PathBuf>,
+    #[structopt(skip)]
+    repo_label: Option<String>,
+    #[structopt(skip)]
+    stdout_header_written: bool,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Path to the output file, the standard output if `-` or not given"
+    )]
+    output: Option<PathBuf>,
+    #[structopt(
+        long,
+        use_delimiter = true,
+        value_delimiter = ",",
+        required_unless_one = &["languages", "auto-languages", "include-filename"],
+        help = "List of file extensions (comma-separated)"
+    )]
+    extensions: Vec<String>,
+    #[structopt(
+        long,
+        use_delimiter = true,
+        value_delimiter = ",",
+        help = "List of full file names to target as well, such as Makefile (comma-separated)"
+    )]
+    include_filename: Vec<String>,
+    #[structopt(
+        long,
+        use_delimiter = true,
+        value_delimiter = ",",
+        help = "List of languages whose file extensions are targeted (comma-separated)"
+    )]
+    languages: Vec<String>,
+    #[structopt(long, help = "Size of the dataset")]
+    size: usize,
+    #[structopt(
+        long,
+        default_value = "8",
+        help = "Minimum commit message length, in characters by default"
+    )]
+    message_len_min: usize,
+    #[structopt(
+        long,
+        default_value = "64",
+        help = "Maximum commit message length, in characters by default"
+    )]
+    message_len_max: usize,
+    #[structopt(
+        long,
+        help = "Minimum commit changes length [default: 1, or per language]"
+    )]
+    changes_len_min: Option<usize>,
+    #[structopt(
+        long,
+        help = "Maximum commit changes length [default: 1024, or per language]"
+    )]
+    changes_len_max: Option<usize>,
+    #[structopt(long, help = "Show progress bar")]
+    show_progress: bool,
+    #[structopt(
+        long,
+        use_delimiter = true,
+        value_delimiter = ",",
+        help = "List of full commit ids to exclude (comma-separated)"
+    )]
+    exclude_commits: Vec<String>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Path to a file of full commit ids to exclude (one per line)"
+    )]
+    exclude_commits_file: Option<PathBuf>,
+    #[structopt(long, help = "Skip commits with templated messages")]
+    skip_templated: bool,
+    #[structopt(
+        long = "template-pattern",
+        number_of_values = 1,
+        help = "Regex matching a commit message template line (repeatable, replaces defaults)"
+    )]
+    template_patterns: Vec<String>,
+    #[structopt(long, help = "Include the total number of commits of the author")]
+    include_author_experience: bool,
+    #[structopt(long, help = "Trim trailing whitespace from commit changes")]
+    trim_changes_trailing: bool,
+    #[structopt(long, help = "Allow extracting from a shallow clone")]
+    allow_shallow: bool,
+    #[structopt(
+        long,
+        help = "Detect copied files, including copies of unmodified files (slower)"
+    )]
+    detect_copies: bool,
+    #[structopt(
+        long,
+        conflicts_with = "detect-copies",
+        help = "Do not detect renamed files, recorded as a deletion and an addition instead"
+    )]
+    no_rename_detection: bool,
+    #[structopt(
+        long,
+        help = "Lock the output file while writing, for concurrent runs appending to it"
+    )]
+    append_lock: bool,
+    #[structopt(
+        long,
+        default_value = "true",
+        parse(try_from_str),
+        help = "Write the header to a new output file even if no commit is saved (true|false)"
+    )]
+    header_on_empty: bool,
+    #[structopt(long, help = "Include the 0-based index of the commit in the dataset")]
+    include_index: bool,
+    #[structopt(long, help = "Fail if a file with a target extension is binary")]
+    fail_on_binary: bool,
+    #[structopt(
+        long,
+        help = "Fail with exit status 2 if no commit is saved, as when the filters are too strict"
+    )]
+    fail_on_empty: bool,
+    #[structopt(
+        long,
+        help = "Replace invalid UTF-8 in the changes of target files instead of skipping the commit"
+    )]
+    lossy: bool,
+    #[structopt(
+        long,
+        default_value = "true",
+        parse(try_from_str),
+        help = "Skip commits where a file with a target extension is binary (true|false)"
+    )]
+    skip_binary: bool,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Path to a file of saved commit ids, to skip them when resuming in a later run"
+    )]
+    resume_state: Option<PathBuf>,
+    #[structopt(long, help = "Mask issue and ticket identifiers in commit messages")]
+    mask_identifiers: bool,
+    #[structopt(
+        long,
+        requires = "mask-identifiers",
+        help = "Also mask identifiers in commit changes"
+    )]
+    mask_identifiers_in_changes: bool,
+    #[structopt(
+        long = "mask-pattern",
+        number_of_values = 1,
+        help = "Identifier regex and its placeholder as REGEX=PLACEHOLDER (repeatable, replaces defaults)"
+    )]
+    mask_patterns: Vec<String>,
+    #[structopt(long, help = "Maximum number of changed lines in a single file")]
+    max_file_lines: Option<usize>,
+    #[structopt(
+        long,
+        help = "Maximum number of lines of the diff of a single file, headers and context lines included"
+    )]
+    max_file_diff_lines: Option<usize>,
+    #[structopt(
+        long,
+        requires = "test-output",
+        help = "Fraction of the commits saved to the test output file (0.0 to 1.0)"
+    )]
+    split: Option<f64>,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        requires = "split",
+        help = "Path to the test output file"
+    )]
+    test_output: Option<PathBuf>,
+    #[structopt(
+        long,
+        help = "Seed of the split, independent of the sampling (defaults to the value of --seed)"
+    )]
+    split_seed: Option<u64>,
+    #[structopt(long, help = "Ignore changes to database migration files")]
+    skip_migrations: bool,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Glob matching database migration file paths (repeatable, replaces defaults)"
+    )]
+    migration_patterns: Vec<String>,
+    #[structopt(
+        long,
+        default_value = "true",
+        parse(try_from_str),
+        help = "Ignore changes to generated files, such as lockfiles and dependencies (true|false)"
+    )]
+    skip_generated: bool,
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Glob matching generated file paths (repeatable, extends defaults)"
+    )]
+    generated_pattern: Vec<String>,
+    #[structopt(
+        long,
+        help = "Include a preview of the first N characters of commit changes"
+    )]
+    preview_len: Option<usize>,
+    #[structopt(long, help = "Maximum number of commits saved per calendar day")]
+    max_per_day: Option<usize>,
+    #[structopt(long, help = "Sync the output files to disk after writing (slower)")]
+    fsync: bool,
+    #[structopt(long, help = "Target the most common languages of the files at HEAD")]
+    auto_languages: bool,
+    #[structopt(
+        long,
+        default_value = "3",
+        help = "Number of languages targeted with --auto-languages"
+    )]
+    auto_languages_count: usize,
+    #[structopt(
+        long,
+        help = "Allocate the dataset size across extensions in proportion to their qualifying commits"
+    )]
+    proportional: bool,
+    #[structopt(long, help = "Minimum number of distinct characters in commit message")]
+    min_unique_chars: Option<usize>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Rotate to a new numbered output file once the current one reaches this size (e.g. 100MB)"
+    )]
+    rotate_size: Option<u64>,
+    #[structopt(
+        long,
+        help = "Diff each commit against the tree of this fixed reference instead of its parent"
+    )]
+    baseline_ref: Option<String>,
+    #[structopt(long, help = "Include the committer name and email")]
+    include_committer: bool,
+    #[structopt(long, help = "Skip commits adding merge conflict markers")]
+    skip_conflict_markers: bool,
+    #[structopt(
+        long,
+        possible_values = &["author"],
+        requires = "partitions",
+        help = "Partition the output files by the hash of this key"
+    )]
+    partition_by: Option<String>,
+    #[structopt(
+        long,
+        requires = "partition-by",
+        help = "Number of output file partitions"
+    )]
+    partitions: Option<usize>,
+    #[structopt(long, help = "Include the detected language of the commit message")]
+    include_message_lang: bool,
+    #[structopt(
+        long,
+        default_value = DEFAULT_PROGRESS_TEMPLATE,
+        help = "Template of the progress bar (see the indicatif documentation)"
+    )]
+    progress_template: String,
+    #[structopt(long, help = "Skip deletions of files with target extensions")]
+    skip_deletions: bool,
+    #[structopt(
+        long,
+        default_value = "commit",
+        possible_values = &["commit", "content"],
+        help = "Skip the whole commit or only the content of deleted files"
+    )]
+    deletions_mode: String,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Write a JSON schema describing the output columns to this file"
+    )]
+    schema_out: Option<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Number of retries with exponential backoff when opening the Git repository fails"
+    )]
+    open_retries: u32,
+    #[structopt(
+        long,
+        default_value = " ",
+        help = "Prefix of the context lines of commit changes, in place of a space"
+    )]
+    context_prefix: String,
+    #[structopt(
+        long,
+        help = "Maximum number of distinct commit authors in the dataset"
+    )]
+    max_authors: Option<usize>,
+    #[structopt(
+        long,
+        default_value = "frequency",
+        possible_values = &["frequency", "encounter"],
+        help = "Select the most prolific authors or the first ones encountered"
+    )]
+    authors_selection: String,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        alias = "stats-json",
+        help = "Write the statistics of the run to this file as JSON"
+    )]
+    stats_out: Option<PathBuf>,
+    #[structopt(
+        long,
+        conflicts_with_all = &[
+            "include-author-experience",
+            "include-committer",
+            "include-message-lang",
+            "include-depth",
+            "partition-by",
+        ],
+        help = "Extract the uncommitted changes of the working directory as a single record, ignoring --size"
+    )]
+    workdir: bool,
+    #[structopt(
+        long,
+        help = "Skip commits whose normalized message duplicates the one of a saved commit"
+    )]
+    dedup_messages_normalized: bool,
+    #[structopt(
+        long,
+        default_value = "basic",
+        possible_values = &["basic", "stem"],
+        help = "Normalize messages by lowercasing and stripping punctuation, then optionally stemming words"
+    )]
+    dedup_normalization: String,
+    #[structopt(
+        long,
+        default_value = "bytes",
+        possible_values = &["bytes", "chars", "lines"],
+        help = "Unit of the commit changes length bounds"
+    )]
+    changes_len_unit: String,
+    #[structopt(
+        long,
+        default_value = "chars",
+        possible_values = &["bytes", "chars", "lines"],
+        help = "Unit of the commit message length bounds"
+    )]
+    message_len_unit: String,
+    #[structopt(long, help = "Skip commits reverted by a later commit")]
+    skip_reverted: bool,
+    #[structopt(
+        long,
+        default_value = "64KB",
+        parse(try_from_str = parse_size),
+        help = "Size of the write buffer of the output files (e.g. 1MB)"
+    )]
+    write_buffer_size: u64,
+    #[structopt(
+        long,
+        help = "Require every changed file to have a target extension, overriding the flags ignoring some files"
+    )]
+    strict: bool,
+    #[structopt(
+        long,
+        conflicts_with = "strict",
+        help = "Keep commits also changing files with other extensions, with the changes of the target files only"
+    )]
+    allow_mixed: bool,
+    #[structopt(
+        long,
+        default_value = "raw",
+        possible_values = &["raw", "applicable"],
+        help = "Format of commit changes, raw diff lines or a patch that can be applied with `git apply`"
+    )]
+    patch_format: String,
+    #[structopt(
+        long,
+        help = "Minimum number of distinct target extensions changed in a commit"
+    )]
+    min_languages: Option<usize>,
+    #[structopt(
+        long,
+        help = "Minimum number of files with target extensions changed in a commit"
+    )]
+    min_files: Option<usize>,
+    #[structopt(
+        long,
+        help = "Maximum number of files with target extensions changed in a commit"
+    )]
+    max_files: Option<usize>,
+    #[structopt(
+        long,
+        conflicts_with_all = &["rotate-size", "repos-file"],
+        help = "Write the output files as pretty-printed JSON arrays, for debugging"
+    )]
+    json_pretty: bool,
+    #[structopt(
+        long,
+        default_value = "commit_message",
+        help = "Name of the commit message column"
+    )]
+    message_column: String,
+    #[structopt(
+        long,
+        default_value = "commit_changes",
+        help = "Name of the commit changes column"
+    )]
+    changes_column: String,
+    #[structopt(
+        long = "rename-column",
+        number_of_values = 1,
+        help = "Rename an output column, as `<name>=<new name>` (repeatable)"
+    )]
+    rename_columns: Vec<String>,
+    #[structopt(
+        long,
+        help = "Check that the commits of the resume state or the checkpoint are still reachable from the ref"
+    )]
+    resume_verify: bool,
+    #[structopt(
+        long,
+        help = "Drop changed lines that are whitespace-only or only differ in whitespace from a changed line"
+    )]
+    drop_whitespace_only_lines: bool,
+    #[structopt(
+        long,
+        help = "Include the depth of the commit, its number of generations from a root commit"
+    )]
+    include_depth: bool,
+    #[structopt(
+        long,
+        default_value = "csv",
+        possible_values = &["csv", "jsonl", "null-delimited"],
+        help = "Format of the output files, CSV, JSON objects one per line, or fields and records terminated by NUL"
+    )]
+    format: String,
+    #[structopt(
+        long,
+        default_value = ",",
+        parse(try_from_str = parse_delimiter),
+        help = "Delimiter of the fields of the CSV format, a single byte, or `\\t` for a tab"
+    )]
+    delimiter: u8,
+    #[structopt(
+        long,
+        default_value = "necessary",
+        possible_values = &["always", "necessary", "never"],
+        help = "Quoting of the fields of the CSV format (always|necessary|never)"
+    )]
+    quote_style: String,
+    #[structopt(long, help = "Skip commits whose message is a bare file name")]
+    skip_filename_messages: bool,
+    #[structopt(
+        long,
+        help = "Include the function contexts of the hunk headers of commit changes"
+    )]
+    include_function_context: bool,
+    #[structopt(
+        long,
+        help = "Abort once this number of commits in a row are skipped without any saved"
+    )]
+    max_consecutive_skips: Option<usize>,
+    #[structopt(
+        long,
+        conflicts_with = "workdir",
+        help = "Stop once this number of commits of the history walk are scanned, whatever the number saved"
+    )]
+    max_scan: Option<usize>,
+    #[structopt(
+        long = "ref",
+        default_value = "HEAD",
+        help = "Branch, tag or commit to walk the history from"
+    )]
+    git_ref: String,
+    #[structopt(
+        long,
+        possible_values = &["base64"],
+        help = "Encode commit changes and their preview, to be decoded by consumers"
+    )]
+    encode_changes: Option<String>,
+    #[structopt(
+        long,
+        possible_values = &["base64"],
+        help = "Encode commit messages, to be decoded by consumers"
+    )]
+    encode_message: Option<String>,
+    #[structopt(
+        long,
+        conflicts_with = "skip-deletions",
+        help = "Only save commits adding new files with target extensions, without changing existing ones"
+    )]
+    only_additions: bool,
+    #[structopt(
+        long,
+        conflicts_with = "workdir",
+        help = "Range of commits to walk instead of the history of the ref, as `A..B` or `A...B`"
+    )]
+    range: Option<String>,
+    #[structopt(
+        long,
+        help = "Number of threads computing diffs, defaulting to the number of logical CPUs"
+    )]
+    jobs: Option<usize>,
+    #[structopt(
+        long,
+        conflicts_with = "json-pretty",
+        help = "Skip commits whose message and changes match a record of the existing output files"
+    )]
+    dedup: bool,
+    #[structopt(long, help = "Leave out the commit hash column")]
+    no_hash: bool,
+    #[structopt(
+        long,
+        conflicts_with = "workdir",
+        help = "Include the author name and email and the commit time"
+    )]
+    include_metadata: bool,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_since),
+        help = "Only save commits authored on or after this date (e.g. 2023-01-01), in UTC unless an offset is given"
+    )]
+    since: Option<i64>,
+    #[structopt(
+        long,
+        parse(try_from_str = parse_until),
+        help = "Only save commits authored on or before this date (e.g. 2023-12-31), in UTC unless an offset is given"
+    )]
+    until: Option<i64>,
+    #[structopt(
+        long,
+        default_value = "(?i)bot",
+        help = "Regex matched against the author name and email of bot commits"
+    )]
+    bot_pattern: String,
+    #[structopt(long, help = "Save bot commits as well")]
+    no_bot_filter: bool,
+    #[structopt(
+        long = "include-path",
+        number_of_values = 1,
+        help = "Glob matching the paths of the files to analyze, others being ignored (repeatable)"
+    )]
+    include_paths: Vec<String>,
+    #[structopt(
+        long = "exclude-path",
+        number_of_values = 1,
+        help = "Glob matching the paths of the files to ignore, even if included (repeatable)"
+    )]
+    exclude_paths: Vec<String>,
+    #[structopt(
+        long,
+        conflicts_with = "workdir",
+        help = "Walk the history from the oldest commit instead of the newest"
+    )]
+    reverse: bool,
+    #[structopt(
+        long,
+        default_value = "time",
+        possible_values = &["time", "topological", "none"],
+        help = "Order of the commits in the history walk"
+    )]
+    sort: String,
+    #[structopt(
+        long,
+        conflicts_with = "workdir",
+        help = "Fraction of the qualifying commits randomly kept before counting toward the size (0.0 to 1.0)"
+    )]
+    sample: Option<f64>,
+    #[structopt(
+        long,
+        default_value = "0",
+        help = "Seed of the sampling, the same seed always keeping the same commits"
+    )]
+    seed: u64,
+    #[structopt(
+        long,
+        help = "Truncate commit changes longer than the maximum length instead of skipping the commit"
+    )]
+    truncate_changes: bool,
+    #[structopt(
+        long,
+        help = "Include the numbers of lines added and removed in the commit changes"
+    )]
+    include_stats: bool,
+    #[structopt(
+        long,
+        help = "Save the changes of each file with a target extension as its own record, the size counting files"
+    )]
+    per_file: bool,
+    #[structopt(
+        long,
+        help = "Only keep added, removed and context lines in commit changes, without file and hunk headers"
+    )]
+    content_only: bool,
+    #[structopt(
+        long,
+        conflicts_with = "removed-only",
+        help = "Only keep added lines in commit changes"
+    )]
+    added_only: bool,
+    #[structopt(long, help = "Only keep removed lines in commit changes")]
+    removed_only: bool,
+    #[structopt(
+        long,
+        help = "Number of unchanged lines shown around each hunk of commit changes, defaulting to 3"
+    )]
+    context_lines: Option<u32>,
+    #[structopt(
+        long = "message-include",
+        number_of_values = 1,
+        help = "Regex matching the commit messages to save (repeatable, any of them)"
+    )]
+    message_includes: Vec<String>,
+    #[structopt(
+        long = "message-exclude",
+        number_of_values = 1,
+        help = "Regex matching the commit messages to skip, even if included (repeatable, extends defaults)"
+    )]
+    message_excludes: Vec<String>,
+    #[structopt(
+        long,
+        help = "Do not skip merge messages by default, only those matching --message-exclude"
+    )]
+    no_default_message_exclude: bool,
+    #[structopt(
+        long,
+        help = "Include the body of the commit message, after its summary"
+    )]
+    include_body: bool,
+    #[structopt(
+        long,
+        default_value = "skip",
+        possible_values = &["skip", "first-parent", "parents"],
+        help = "Skip merge commits, diff them against their first parent, or process their parents instead"
+    )]
+    merge_strategy: String,
+    #[structopt(
+        long,
+        conflicts_with = "workdir",
+        help = "Only follow the first parent of merge commits in the history walk"
+    )]
+    first_parent: bool,
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &["json-pretty", "append-lock", "workdir", "repos-file"],
+        help = "Path to a checkpoint file to resume an interrupted run from"
+    )]
+    checkpoint: Option<PathBuf>,
+    #[structopt(
+        long,
+        default_value = "100",
+        help = "Number of records saved between two checkpoints"
+    )]
+    checkpoint_interval: usize,
+}
+
+/// Output path standing for the standard output.
+const STDOUT_OUTPUT: &str = "-";
+
+/// Initial delay before retrying to open the Git repository, doubled after each attempt.
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Number of commits per thread whose diffs are computed ahead in a batch.
+const PREFETCH_BATCH_SIZE_PER_THREAD: usize = 16;
+
+/// Version of the output schema, bumped on any change to the columns.
+const SCHEMA_VERSION: u32 = 1;
+
+impl Config {
+    /// Parse the configuration from the command line, completed with the options of the
+    /// `--config` file missing from it.
+    pub fn from_args_with_file() -> Result<Config> {
+        let args = with_config_file_args(std::env::args_os().collect())?;
+        Ok(Config::from_iter(args))
+    }
+}
+
+/// Builder of a `Config`, taking the same options as the command line.
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    /// Command line arguments of the configuration.
+    args: Vec<OsString>,
+}
+
+impl ConfigBuilder {
+    /// Create a new builder to extract data from the given Git repository.
+    pub fn new(repository: impl AsRef<Path>) -> ConfigBuilder {
+        ConfigBuilder {
+            args: vec![OsString::from("gitex")],
+        }
+        .option("repository", repository.as_ref())
+    }
+
+    /// Set the output file to save the dataset to.
+    pub fn output(self, path: impl AsRef<Path>) -> ConfigBuilder {
+        self.option("output", path.as_ref())
+    }
+
+    /// Set the file extensions to target.
+    pub fn extensions(self, extensions: &[&str]) -> ConfigBuilder {
+        self.option("extensions", extensions.join(","))
+    }
+
+    /// Set the size of the dataset.
+    pub fn size(self, size: usize) -> ConfigBuilder {
+        self.option("size", size.to_string())
+    }
+
+    /// Set an option by its command line name, e.g. `message-len-max`.
+    pub fn option(mut self, name: &str, value: impl AsRef<OsStr>) -> ConfigBuilder {
+        let mut arg = OsString::from(format!("--{name}="));
+        arg.push(value);
+        self.args.push(arg);
+        self
+    }
+
+    /// Set a flag by its command line name, e.g. `skip-templated`.
+    pub fn flag(mut self, name: &str) -> ConfigBuilder {
+        self.args.push(OsString::from(format!("--{name}")));
+        self
+    }
+
+    /// Build the configuration, checking the options as the command line does.
+    pub fn build(self) -> Result<Config> {
+        Config::from_iter_safe(with_config_file_args(self.args)?)
+            .map_err(|e| anyhow!("invalid configuration ({e})"))
+    }
+}
+
+/// Default template of the progress bar.
+const DEFAULT_PROGRESS_TEMPLATE: &str =
+    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len}";
+
+/// Template of the spinner counting the commits scanned.
+const SCAN_PROGRESS_TEMPLATE: &str = "{spinner:.green} [{elapsed_precise}] {pos} commits scanned";
+
+/// Default patterns matching lines left over from commit message templates.
+const DEFAULT_TEMPLATE_PATTERNS: &[&str] = &[
+    r"^# Please enter the commit message",
+    r"^# Lines starting with '#' will be ignored",
+    r"^# On branch ",
+    r"^# Changes to be committed:",
+    r"^# -+ >8 -+$",
+    r"^<type>(\(<scope>\))?: <(subject|description)>$",
+];
+
+/// Default patterns matching the commit messages to skip, those of merges.
+const DEFAULT_MESSAGE_EXCLUDE_PATTERNS: &[&str] = &["^Merge pull request", "^Merge branch"];
+
+/// Default minimum commit changes length.
+const DEFAULT_CHANGES_LEN_MIN: usize = 1;
+
+/// Default maximum commit changes length.
+const DEFAULT_CHANGES_LEN_MAX: usize = 1024;
+
+/// Line ending truncated commit changes.
+const TRUNCATION_MARKER: &str = "...[truncated]";
+
+/// Default patterns matching identifiers, with their placeholders.
+const DEFAULT_MASK_PATTERNS: &[(&str, &str)] = &[
+    (r"\b[A-Z][A-Z0-9]+-[0-9]+\b", "<TICKET>"),
+    (r"#[0-9]+\b", "#<NUM>"),
+];
+
+/// Keys of names shaped like ticket keys but naming encodings, algorithms or standards, such as
+/// `UTF-8`, `SHA-256` or `ISO-8601`, which are never masked.
+const NON_TICKET_KEYS: &[&str] = &[
+    "AES", "CP", "CRC", "HTTP", "IEC", "IEEE", "ISO", "PEP", "RFC", "RSA", "SHA", "SSL", "TLS",
+    "UCS", "UTF",
+];
+
+/// Default patterns matching database migration file paths.
+const DEFAULT_MIGRATION_PATTERNS: &[&str] = &[
+    "**/migrations/**",
+    "**/db/migrate/**",
+    "**/alembic/versions/**",
+];
+
+/// Default patterns matching generated file paths, lockfiles, installed dependencies and minified
+/// files.
+const DEFAULT_GENERATED_PATTERNS: &[&str] = &[
+    "**/package-lock.json",
+    "**/npm-shrinkwrap.json",
+    "**/yarn.lock",
+    "**/pnpm-lock.yaml",
+    "**/Cargo.lock",
+    "**/poetry.lock",
+    "**/Pipfile.lock",
+    "**/composer.lock",
+    "**/Gemfile.lock",
+    "**/go.sum",
+    "**/node_modules/**",
+    "**/*.min.js",
+    "**/*.min.css",
+];
+
+/// Check if a given line is a merge conflict marker: `<<<<<<<`, `=======` or `>>>>>>>`, the first
+/// and last ones being optionally followed by a label.
+fn is_conflict_marker(line: &str) -> bool {
+    let line = line.trim_end_matches(['\r', '\n']);
+    line == "======="
+        || ["<<<<<<<", ">>>>>>>"].iter().any(|marker| {
+            line.strip_prefix(marker)
+                .map(|label| label.is_empty() || label.starts_with(' '))
+                .unwrap_or(false)
+        })
+}
+
+/// Measure the length of a given text in bytes, chars or lines.
+fn measure_len(text: &str, unit: &str) -> usize {
+    match unit {
+        "chars" => text.chars().count(),
+        "lines" => text.lines().count(),
+        _ => text.len(),
+    }
+}
+
+/// Get the length of the truncation marker line in bytes, chars or lines, with the newline
+/// preceding it.
+fn truncation_marker_len(unit: &str) -> usize {
+    match unit {
+        "lines" => 1,
+        _ => TRUNCATION_MARKER.len() + 1,
+    }
+}
+
+/// Truncate a given text to a maximum length in bytes, chars or lines, on a char boundary.
+/// The text is ended by a truncation marker line, counted in the length, so the maximum length
+/// must be at least that of the marker.
+fn truncate_len(text: &str, max_len: usize, unit: &str) -> String {
+    let budget = max_len.saturating_sub(truncation_marker_len(unit));
+    let end = match unit {
+        "chars" => text
+            .char_indices()
+            .nth(budget)
+            .map_or(text.len(), |(index, _)| index),
+        "lines" => text.split_inclusive('\n').take(budget).map(str::len).sum(),
+        _ => (0..=budget.min(text.len()))
+            .rev()
+            .find(|index| text.is_char_boundary(*index))
+            .unwrap_or(0),
+    };
+
+    let mut truncated = text[..end].to_string();
+    if !truncated.is_empty() && !truncated.ends_with('\n') {
+        truncated.push('\n');
+    }
+    truncated.push_str(TRUNCATION_MARKER);
+    truncated
+}
+
+/// Encode a given text with the given encoding, if any.
+fn encode_text(text: String, encoding: Option<&str>) -> String {
+    match encoding {
+        Some("base64") => BASE64_STANDARD.encode(text),
+        _ => text,
+    }
+}
+
+/// Format a given Git time as an RFC 3339 timestamp with its timezone offset,
+/// e.g. `2023-05-01T12:34:56+02:00`.
+fn format_time(time: Time) -> String {
+    let offset = i64::from(time.offset_minutes());
+    let local_seconds = time.seconds() + offset * 60;
+    let seconds = local_seconds.rem_euclid(86400);
+
+    // Convert the number of days since the epoch to a civil date
+    let days = local_seconds.div_euclid(86400) + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}{sign}{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Get the target extension of a file path, the longest suffix of its lowercase file name after a
+/// dot among the target extensions, e.g. `d.ts` or else `ts` for `types.d.ts`, and `md` for
+/// `README.MD`. The leading dot of a hidden file does not start an extension.
+/// A file whose whole lowercase name is a target file name, such as `makefile`, has its name as
+/// extension.
+fn get_target_extension(
+    file_extensions: &HashSet<OsString>,
+    file_names: &HashSet<OsString>,
+    path: &Path,
+) -> Option<OsString> {
+    let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+    if file_names.contains(OsStr::new(&file_name)) {
+        return Some(OsString::from(file_name));
+    }
+    file_name
+        .match_indices('.')
+        .filter(|(index, _)| *index > 0)
+        .map(|(index, _)| OsString::from(&file_name[index + 1..]))
+        .find(|ext| file_extensions.contains(ext))
+}
+
+/// Push a block of changed lines to commit changes, dropping whitespace-only lines and pairs of
+/// removed and added lines only differing in whitespace.
+/// Lines of origins not kept are dropped as well.
+fn push_changed_lines(
+    file: &mut FileChanges,
+    changed_lines: &mut Vec<(char, String)>,
+    is_origin_kept: impl Fn(char) -> bool,
+) {
+    let keys: Vec<String> = changed_lines
+        .iter()
+        .map(|(_, content)| content.split_whitespace().collect())
+        .collect();
+
+    // Drop whitespace-only lines
+    let mut dropped: Vec<bool> = keys.iter().map(String::is_empty).collect();
+
+    // Drop removed lines matching an added line modulo whitespace, along with the added line
+    for removed in 0..changed_lines.len() {
+        if dropped[removed] || changed_lines[removed].0 != '-' {
+            continue;
+        }
+        if let Some(added) = (0..changed_lines.len()).find(|&added| {
+            !dropped[added] && changed_lines[added].0 == '+' && keys[added] == keys[removed]
+        }) {
+            dropped[removed] = true;
+            dropped[added] = true;
+        }
+    }
+
+    for ((origin, content), dropped) in changed_lines.drain(..).zip(dropped) {
+        if !dropped && is_origin_kept(origin) {
+            file.line_stats.count(origin);
+            file.content.push(origin);
+            file.content.push_str(&content);
+        }
+    }
+}
+
+/// Hash the message and changes of a record.
+fn hash_record(commit_message: &str, commit_changes: &str) -> u64 {
+    fnv1a(
+        commit_message
+            .as_bytes()
+            .iter()
+            .chain(b"\0")
+            .chain(commit_changes.as_bytes()),
+    )
+}
+
+/// Compute the FNV-1a hash of the given bytes, stable across runs and platforms.
+fn fnv1a<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u64 {
+    bytes
+        .into_iter()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+struct Language {
+    /// Name of the language.
+    name: &'static str,
+    /// File extensions of the language.
+    extensions: &'static [&'static str],
+    /// Default minimum commit changes length.
+    changes_len_min: usize,
+    /// Default maximum commit changes length.
+    changes_len_max: usize,
+}
+
+/// Languages that can be targeted with their default commit changes length bounds.
+const LANGUAGES: &[Language] = &[
+    Language {
+        name: "c",
+        extensions: &["c", "h"],
+        changes_len_min: 1,
+        changes_len_max: 1536,
+    },
+    Language {
+        name: "cpp",
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        changes_len_min: 1,
+        changes_len_max: 2048,
+    },
+    Language {
+        name: "go",
+        extensions: &["go"],
+        changes_len_min: 1,
+        changes_len_max: 1536,
+    },
+    Language {
+        name: "java",
+        extensions: &["java"],
+        changes_len_min: 1,
+        changes_len_max: 2048,
+    },
+    Language {
+        name: "javascript",
+        extensions: &["js", "jsx", "mjs", "cjs"],
+        changes_len_min: 1,
+        changes_len_max: 1024,
+    },
+    Language {
+        name: "python",
+        extensions: &["py"],
+        changes_len_min: 1,
+        changes_len_max: 1024,
+    },
+    Language {
+        name: "ruby",
+        extensions: &["rb"],
+        changes_len_min: 1,
+        changes_len_max: 1024,
+    },
+    Language {
+        name: "rust",
+        extensions: &["rs"],
+        changes_len_min: 1,
+        changes_len_max: 1536,
+    },
+    Language {
+        name: "shell",
+        extensions: &["sh", "bash", "zsh"],
+        changes_len_min: 1,
+        changes_len_max: 768,
+    },
+    Language {
+        name: "typescript",
+        extensions: &["ts", "tsx"],
+        changes_len_min: 1,
+        changes_len_max: 1024,
+    },
+];
+
+struct CommitChanges {
+    /// Contains changes of each file with a target extension, in diff order.
+    files: Vec<FileChanges>,
+    /// Set of target file extensions changed.
+    extensions: HashSet<OsString>,
+}
+
+struct FileChanges {
+    /// Path of the file changed.
+    path: PathBuf,
+    /// Extension of the file changed.
+    extension: OsString,
+    /// Contains changes of the file.
+    content: String,
+    /// Distinct function contexts of the hunks of the file, if included.
+    function_contexts: Vec<String>,
+    /// Numbers of lines added and removed in the changes.
+    line_stats: LineStats,
+}
+
+impl FileChanges {
+    /// Join the changes of the given files, attributed to the first one.
+    fn join(files: Vec<FileChanges>) -> Option<FileChanges> {
+        files.into_iter().reduce(|mut joined, file| {
+            joined.content.push_str(&file.content);
+            for function_context in file.function_contexts {
+                if !joined.function_contexts.contains(&function_context) {
+                    joined.function_contexts.push(function_context);
+                }
+            }
+            joined.line_stats.added += file.line_stats.added;
+            joined.line_stats.removed += file.line_stats.removed;
+            joined
+        })
+    }
+}
+
+/// Numbers of lines added and removed in commit changes.
+#[derive(Default)]
+struct LineStats {
+    /// Number of lines added.
+    added: usize,
+    /// Number of lines removed.
+    removed: usize,
+}
+
+impl LineStats {
+    /// Count a line of commit changes with the given origin.
+    fn count(&mut self, origin: char) {
+        match origin {
+            '+' => self.added += 1,
+            '-' => self.removed += 1,
+            _ => {}
+        }
+    }
+}
+
+enum Changes {
+    /// Changes of files with target extensions only.
+    Target(CommitChanges),
+    /// No changes in files with target extensions, or changes in files with other extensions.
+    NoTarget,
+    /// Changes in ignored database migration files only.
+    OnlyMigrations,
+    /// Changes in a binary file with a target extension.
+    Binary(PathBuf),
+    /// Too many changed lines in a single file.
+    FileTooLarge(PathBuf),
+    /// Too many lines in the diff of a single file.
+    FileDiffTooLong(PathBuf),
+    /// Merge conflict markers added in a file.
+    ConflictMarkers(PathBuf),
+    /// Invalid UTF-8 in the changes of a file with a target extension.
+    NotUtf8(PathBuf),
+    /// Deletion of a file with a target extension.
+    Deletion(PathBuf),
+    /// Change of an existing file with a target extension, when only additions are saved.
+    NotAddition(PathBuf),
+}
+
+/// Data extracted from a commit.
+#[derive(Serialize, Clone)]
+pub struct Record {
+    /// Contains the commit id.
+    #[serde(skip)]
+    commit_id: Oid,
+    /// Contains the target file extension the commit is attributed to.
+    #[serde(skip)]
+    extension: OsString,
+    /// Contains the index of the output file partition of the record.
+    #[serde(skip)]
+    partition: usize,
+    /// Contains the normalized commit message, if messages are deduplicated.
+    #[serde(skip)]
+    message_key: Option<String>,
+    /// Contains the commit hash, empty for working directory changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_hash: Option<String>,
+    /// Contains the index of the record in the dataset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// Contains a commit message.
+    pub commit_message: String,
+    /// Contains the body of the commit message, after its summary.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_body: Option<String>,
+    /// Contains commit changes.
+    pub commit_changes: String,
+    /// Contains the path of the file changed, if records are saved per file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// Contains a preview of commit changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes_preview: Option<String>,
+    /// Contains the total number of commits of the author.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_commits: Option<usize>,
+    /// Contains the author name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+    /// Contains the author email.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+    /// Contains the commit time, as an RFC 3339 timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_time: Option<String>,
+    /// Contains the committer name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub committer_name: Option<String>,
+    /// Contains the committer email.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub committer_email: Option<String>,
+    /// Contains the ISO 639-3 code of the commit message language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_lang: Option<String>,
+    /// Contains the label of the Git repository.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    /// Contains the depth of the commit from a root commit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<usize>,
+    /// Contains the function contexts of the hunks of commit changes, one per line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_context: Option<String>,
+    /// Contains the number of lines added in commit changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines_added: Option<usize>,
+    /// Contains the number of lines removed in commit changes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines_removed: Option<usize>,
+}
+
+impl Record {
+    /// Get the fields of the record, in column order.
+    pub fn fields(&self) -> Vec<String> {
+        let mut fields = Vec::new();
+        if let Some(commit_hash) = &self.commit_hash {
+            fields.push(commit_hash.clone());
+        }
+        if let Some(index) = self.index {
+            fields.push(index.to_string());
+        }
+        fields.push(self.commit_message.clone());
+        if let Some(commit_body) = &self.commit_body {
+            fields.push(commit_body.clone());
+        }
+        fields.push(self.commit_changes.clone());
+        if let Some(file_path) = &self.file_path {
+            fields.push(file_path.clone());
+        }
+        if let Some(changes_preview) = &self.changes_preview {
+            fields.push(changes_preview.clone());
+        }
+        if let Some(author_commits) = self.author_commits {
+            fields.push(author_commits.to_string());
+        }
+        if let Some(author_name) = &self.author_name {
+            fields.push(author_name.clone());
+        }
+        if let Some(author_email) = &self.author_email {
+            fields.push(author_email.clone());
+        }
+        if let Some(commit_time) = &self.commit_time {
+            fields.push(commit_time.clone());
+        }
+        if let Some(committer_name) = &self.committer_name {
+            fields.push(committer_name.clone());
+        }
+        if let Some(committer_email) = &self.committer_email {
+            fields.push(committer_email.clone());
+        }
+        if let Some(message_lang) = &self.message_lang {
+            fields.push(message_lang.clone());
+        }
+        if let Some(repo) = &self.repo {
+            fields.push(repo.clone());
+        }
+        if let Some(depth) = self.depth {
+            fields.push(depth.to_string());
+        }
+        if let Some(function_context) = &self.function_context {
+            fields.push(function_context.clone());
+        }
+        if let Some(lines_added) = self.lines_added {
+            fields.push(lines_added.to_string());
+        }
+        if let Some(lines_removed) = self.lines_removed {
+            fields.push(lines_removed.to_string());
+        }
+        fields
+    }
+}
+
+/// Column of the output dataset, as described in the schema file.
+#[derive(Serialize)]
+struct Column {
+    /// Name of the column.
+    name: String,
+    /// Default name of the column, before renaming.
+    #[serde(skip)]
+    default_name: &'static str,
+    /// Type of the column values.
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// Flag adding the column to the output, if not always present.
+    flag: Option<&'static str>,
+    /// Encoding of the column values, if encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+}
+
+/// Schema of the output dataset.
+#[derive(Serialize)]
+struct Schema {
+    /// Version of the schema.
+    schema_version: u32,
+    /// Columns of the dataset, in order.
+    columns: Vec<Column>,
+}
+
+/// Statistics of a run, as written in the statistics file, summed over the repositories of a
+/// repos file.
+#[derive(Serialize, Default)]
+struct Stats {
+    /// Number of commits scanned by the history walk.
+    commits_scanned: usize,
+    /// Number of commits processed.
+    commits_processed: usize,
+    /// Number of commits saved in the dataset.
+    commits_saved: usize,
+    /// Number of commits skipped per reason.
+    commits_skipped: BTreeMap<&'static str, usize>,
+    /// Whether the scan limit was reached by a history walk.
+    #[serde(skip)]
+    scan_limit_reached: bool,
+    /// Duration of the run in seconds.
+    elapsed_secs: f64,
+    /// Error which terminated the run early, if any.
+    error: Option<String>,
+}
+
+impl Stats {
+    /// Add the statistics of another run, that of another repository.
+    fn add(&mut self, other: Stats) {
+        self.commits_scanned += other.commits_scanned;
+        self.commits_processed += other.commits_processed;
+        self.commits_saved += other.commits_saved;
+        for (reason, count) in other.commits_skipped {
+            *self.commits_skipped.entry(reason).or_insert(0) += count;
+        }
+        self.scan_limit_reached |= other.scan_limit_reached;
+    }
+
+    /// Print the totals of the run, followed by the number of commits skipped per reason, the
+    /// most frequent first.
+    fn print_summary(&self, output: Option<&Path>) -> Result<()> {
+        let mut summary = get_summary_writer(output);
+        writeln!(summary, "Total commits scanned: {}", self.commits_scanned)?;
+        if self.scan_limit_reached {
+            writeln!(summary, "  Scan limit reached (--max-scan)")?;
+        }
+        writeln!(
+            summary,
+            "Total commits processed: {}",
+            self.commits_processed
+        )?;
+        writeln!(summary, "Total commits saved: {}", self.commits_saved)?;
+
+        let mut nb_commits_skipped: Vec<(&str, usize)> = self
+            .commits_skipped
+            .iter()
+            .map(|(reason, count)| (*reason, *count))
+            .collect();
+        nb_commits_skipped.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        for (reason, count) in nb_commits_skipped {
+            writeln!(summary, "  Skipped ({reason}): {count}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the statistics to a file as JSON.
+    fn save(&self, path: &Path) -> Result<()> {
+        let stats_file =
+            File::create(path).map_err(|e| anyhow!("failed to create the stats file ({e})"))?;
+        serde_json::to_writer_pretty(stats_file, self)
+            .map_err(|e| anyhow!("failed to write the stats file ({e})"))?;
+
+        Ok(())
+    }
+}
+
+/// Checkpoint of an interrupted run, to resume from.
+struct Checkpoint {
+    /// Last commit visited by the history walk.
+    commit_oid: Oid,
+    /// Number of commits saved in the dataset.
+    nb_commits_saved: usize,
+}
+
+/// Output file being written to as records are saved.
+struct OutputWriter {
+    /// Path of the output file, before rotation.
+    path: PathBuf,
+    /// Index of the current rotated output file.
+    index: usize,
+    /// Buffered writer of the current rotated output file, or of the standard output.
+    buffer: BufWriter<Box<dyn Write>>,
+    /// Current rotated output file, unless writing to the standard output.
+    file: Option<File>,
+    /// Size of the current rotated output file, buffered bytes included.
+    size: u64,
+    /// Whether the header is still to be written to the current rotated output file.
+    header_pending: bool,
+    /// Objects to write as a JSON array once the dataset is complete.
+    json_objects: Vec<Map<String, Value>>,
+}
+
+/// Files the dataset is written to, kept open during the extraction.
+struct DatasetWriter {
+    /// Output file of each partition.
+    outputs: Vec<OutputWriter>,
+    /// Test output file.
+    test_output: Option<OutputWriter>,
+    /// Resume state file.
+    resume_state: Option<BufWriter<File>>,
+}
+
+/// Analyzer of the diffs of commits, which can be shared between threads.
+struct DiffAnalyzer<'a> {
+    /// Configuration.
+    config: &'a Config,
+    /// Set target of file extensions.
+    file_extensions: &'a HashSet<OsString>,
+    /// Set target of full file names.
+    file_names: &'a HashSet<OsString>,
+    /// Patterns matching database migration file paths.
+    migration_patterns: &'a GlobSet,
+    /// Patterns matching generated file paths.
+    generated_patterns: &'a GlobSet,
+    /// Patterns matching the paths of the files to analyze, if any.
+    include_paths: Option<&'a GlobSet>,
+    /// Patterns matching the paths of the files to ignore.
+    exclude_paths: &'a GlobSet,
+}
+
+impl DiffAnalyzer<'_> {
+    /// Get the options of the diffs to analyze.
+    fn get_diff_options(&self) -> DiffOptions {
+        let mut diff_options = DiffOptions::new();
+        if let Some(context_lines) = self.config.context_lines {
+            diff_options.context_lines(context_lines);
+        }
+        if self.config.detect_copies {
+            // Unmodified files must be part of the diff to be considered as copy sources
+            diff_options.include_unmodified(true);
+        }
+        if self.config.patch_format == "applicable" {
+            // Binary patches can only be applied with their content and full blob ids
+            diff_options.show_binary(true).id_abbrev(40);
+        }
+        diff_options
+    }
+
+    /// Get changes between a given commit tree and its parent tree in a Git repository.
+    fn get_tree_changes(
+        &self,
+        git_repo: &Repository,
+        commit_tree: &Tree,
+        parent_tree: &Tree,
+    ) -> Result<Changes> {
+        // Create a diff representing the difference between the parent tree and the commit tree
+        let diff_output = git_repo
+            .diff_tree_to_tree(
+                Some(parent_tree),
+                Some(commit_tree),
+                Some(&mut self.get_diff_options()),
+            )
+            .map_err(|e| anyhow!("failed to create diff ({e})"))?;
+
+        self.get_diff_changes(diff_output)
+    }
+
+    /// Check if a given file path relative to the repository root is selected by the include and
+    /// exclude path patterns, excluded paths being never selected.
+    fn is_path_selected(&self, file_path: &Path) -> bool {
+        !self.exclude_paths.is_match(file_path)
+            && self
+                .include_paths
+                .is_none_or(|include_paths| include_paths.is_match(file_path))
+    }
+
+    /// Check if lines of a given diff line origin are kept in commit changes.
+    fn is_origin_kept(&self, origin: char) -> bool {
+        if self.config.added_only {
+            origin == '+'
+        } else if self.config.removed_only {
+            origin == '-'
+        } else if self.config.content_only {
+            matches!(origin, '+' | '-' | ' ')
+        } else {
+            true
+        }
+    }
+
+    /// Get changes of a given diff.
+    fn get_diff_changes(&self, mut diff_output: Diff) -> Result<Changes> {
+        let mut files: Vec<FileChanges> = Vec::new();
+        let mut extensions_changed = HashSet::new();
+        let mut file_lines_changed: HashMap<PathBuf, usize> = HashMap::new();
+        let mut file_diff_lines: HashMap<PathBuf, usize> = HashMap::new();
+        let mut rejection = None;
+        let mut files_with_target_extensions_changed = false;
+        let mut files_with_other_extensions_changed = false;
+        let mut migration_files_changed = false;
+        let applicable = self.config.patch_format == "applicable";
+        let mut changed_lines = Vec::new();
+        let is_origin_kept = |origin| self.is_origin_kept(origin);
+
+        // Detect renamed and copied files, the source path is recorded in the file header
+        if !self.config.no_rename_detection {
+            let mut find_options = DiffFindOptions::new();
+            find_options.renames(true);
+            if self.config.detect_copies {
+                find_options.copies(true).copies_from_unmodified(true);
+            }
+            diff_output
+                .find_similar(Some(&mut find_options))
+                .map_err(|e| anyhow!("failed to detect renames ({e})"))?;
+        }
+
+        // Iterate over the diff, analyzing each file changed
+        diff_output
+            .print(git2::DiffFormat::Patch, |delta, _hunk, line_diff| {
+                // Ignore unmodified files included as copy sources
+                if delta.status() == Delta::Unmodified {
+                    return true;
+                }
+                // Check the new path of the file, or its old path if it is deleted
+                if let Some(file_path) = delta.new_file().path().or_else(|| delta.old_file().path())
+                {
+                    // Ignore database migration files
+                    if self.config.skip_migrations
+                        && !self.config.strict
+                        && self.migration_patterns.is_match(file_path)
+                    {
+                        migration_files_changed = true;
+                        return true;
+                    }
+                    // Ignore generated files
+                    if self.config.skip_generated
+                        && !self.config.strict
+                        && self.generated_patterns.is_match(file_path)
+                    {
+                        return true;
+                    }
+                    // Ignore files filtered out by path, unless every file must be a target
+                    if !self.is_path_selected(file_path) && !self.config.strict {
+                        return true;
+                    }
+                    // Check if the file extension matches one of the target file extensions
+                    if let Some(ext) =
+                        get_target_extension(self.file_extensions, self.file_names, file_path)
+                            .filter(|_| self.is_path_selected(file_path))
+                    {
+                        // Check if the file is deleted
+                        if self.config.skip_deletions && delta.status() == Delta::Deleted {
+                            if self.config.deletions_mode == "content" {
+                                return true;
+                            }
+                            rejection = Some(Changes::Deletion(file_path.to_path_buf()));
+                            return false;
+                        }
+                        // Check if the file is added
+                        if self.config.only_additions && delta.status() != Delta::Added {
+                            rejection = Some(Changes::NotAddition(file_path.to_path_buf()));
+                            return false;
+                        }
+                        // Check if the file is binary
+                        if (self.config.fail_on_binary || self.config.skip_binary)
+                            && delta.flags().is_binary()
+                        {
+                            rejection = Some(Changes::Binary(file_path.to_path_buf()));
+                            return false;
+                        }
+                        // Check the number of changed lines in the file
+                        if let Some(max_file_lines) = self.config.max_file_lines {
+                            if matches!(line_diff.origin(), '+' | '-') {
+                                let lines = file_lines_changed
+                                    .entry(file_path.to_path_buf())
+                                    .or_insert(0);
+                                *lines += 1;
+                                if *lines > max_file_lines {
+                                    rejection =
+                                        Some(Changes::FileTooLarge(file_path.to_path_buf()));
+                                    return false;
+                                }
+                            }
+                        }
+                        // Check the number of lines of the file diff, before accumulating them
+                        if let Some(max_file_diff_lines) = self.config.max_file_diff_lines {
+                            let lines = file_diff_lines.entry(file_path.to_path_buf()).or_insert(0);
+                            // A file header spans several lines
+                            *lines += line_diff
+                                .content()
+                                .iter()
+                                .filter(|byte| **byte == b'\n')
+                                .count()
+                                .max(1);
+                            if *lines > max_file_diff_lines {
+                                rejection = Some(Changes::FileDiffTooLong(file_path.to_path_buf()));
+                                return false;
+                            }
+                        }
+                        // Check if a merge conflict marker is added
+                        if self.config.skip_conflict_markers
+                            && line_diff.origin() == '+'
+                            && str::from_utf8(line_diff.content())
+                                .map(is_conflict_marker)
+                                .unwrap_or(false)
+                        {
+                            rejection = Some(Changes::ConflictMarkers(file_path.to_path_buf()));
+                            return false;
+                        }
+                        files_with_target_extensions_changed = true;
+                        if !extensions_changed.contains(&ext) {
+                            extensions_changed.insert(ext.clone());
+                        }
+                        // Start the changes of a new file, after the held lines of the previous one
+                        if files.last().is_none_or(|file| file.path != file_path) {
+                            if let Some(file) = files.last_mut() {
+                                push_changed_lines(file, &mut changed_lines, is_origin_kept);
+                            }
+                            files.push(FileChanges {
+                                path: file_path.to_path_buf(),
+                                extension: ext.clone(),
+                                content: String::new(),
+                                function_contexts: Vec::new(),
+                                line_stats: LineStats::default(),
+                            });
+                        }
+                        let nb_files = files.len();
+                        let file = &mut files[nb_files - 1];
+                        // Check if the line is valid UTF-8, or replace its invalid sequences
+                        let line_diff_content = match str::from_utf8(line_diff.content()) {
+                            Ok(line_diff_content) => Cow::Borrowed(line_diff_content),
+                            Err(_) if self.config.lossy => {
+                                String::from_utf8_lossy(line_diff.content())
+                            }
+                            Err(_) => {
+                                rejection = Some(Changes::NotUtf8(file_path.to_path_buf()));
+                                return false;
+                            }
+                        };
+                        // Get function context of the hunk
+                        if self.config.include_function_context && line_diff.origin() == 'H' {
+                            if let Some(function_context) = line_diff_content
+                                .splitn(3, "@@")
+                                .nth(2)
+                                .map(str::trim)
+                                .filter(|context| !context.is_empty())
+                            {
+                                if !file.function_contexts.iter().any(|c| c == function_context) {
+                                    file.function_contexts.push(function_context.to_string());
+                                }
+                            }
+                        }
+                        // Hold changed lines until the end of the block of changed lines
+                        if self.config.drop_whitespace_only_lines {
+                            if matches!(line_diff.origin(), '+' | '-') {
+                                changed_lines
+                                    .push((line_diff.origin(), line_diff_content.to_string()));
+                                return true;
+                            }
+                            push_changed_lines(file, &mut changed_lines, is_origin_kept);
+                        }
+                        // Ignore lines of other origins than the kept ones, e.g. file headers
+                        if !is_origin_kept(line_diff.origin()) {
+                            return true;
+                        }
+                        // Get commit changes
+                        match line_diff.origin() {
+                            ' ' if applicable => file.content.push(' '),
+                            ' ' => file.content.push_str(&self.config.context_prefix),
+                            // Other lines than added, removed and context lines carry their
+                            // own text, e.g. file and hunk headers
+                            '+' | '-' => {
+                                file.line_stats.count(line_diff.origin());
+                                file.content.push(line_diff.origin());
+                            }
+                            _ if applicable => {}
+                            origin => file.content.push(origin),
+                        }
+                        file.content.push_str(&line_diff_content);
+                    } else if !self.config.allow_mixed {
+                        files_with_other_extensions_changed = true;
+                        // The commit is already rejected, unless a binary file must be found
+                        if !self.config.fail_on_binary {
+                            rejection = Some(Changes::NoTarget);
+                            return false;
+                        }
+                    }
+                }
+                true
+            })
+            .or_else(|e| match rejection {
+                // The iteration was stopped on a rejected file
+                Some(_) => Ok(()),
+                None => Err(anyhow!("failed to parse diff output ({e})")),
+            })?;
+        if let Some(file) = files.last_mut() {
+            push_changed_lines(file, &mut changed_lines, is_origin_kept);
+        }
+
+        // Check if a file rejected the commit
+        if let Some(rejection) = rejection {
+            return Ok(rejection);
+        }
+
+        // Check if only ignored database migration files were changed
+        if migration_files_changed
+            && !files_with_target_extensions_changed
+            && !files_with_other_extensions_changed
+        {
+            return Ok(Changes::OnlyMigrations);
+        }
+
+        // Check if only files with target extensions were changed
+        if files.is_empty() || files_with_other_extensions_changed {
+            return Ok(Changes::NoTarget);
+        }
+
+        Ok(Changes::Target(CommitChanges {
+            files,
+            extensions: extensions_changed,
+        }))
+    }
+}
+
+/// Extractor of data from the commits of a Git repository.
+pub struct Extractor {
+    /// Configuration.
+    config: Config,
+    /// The Git repository to analyze.
+    git_repo: Repository,
+    /// Set target of file extensions.
+    file_extensions: HashSet<OsString>,
+    /// Set target of full file names.
+    file_names: HashSet<OsString>,
+    /// Targeted languages.
+    languages: Vec<&'static Language>,
+    /// Tree id of the fixed baseline to diff commits against.
+    baseline_tree_id: Option<Oid>,
+    /// Set of commit ids to exclude.
+    excluded_commit_ids: HashSet<Oid>,
+    /// Set of commit ids saved in previous runs.
+    resumed_commit_ids: HashSet<Oid>,
+    /// Set of commit ids reverted by a later commit.
+    reverted_commit_ids: HashSet<Oid>,
+    /// Patterns matching commit message template lines.
+    template_patterns: Vec<Regex>,
+    /// Patterns matching the commit messages to save, if any.
+    message_include_patterns: Vec<Regex>,
+    /// Patterns matching the commit messages to skip.
+    message_exclude_patterns: Vec<Regex>,
+    /// Patterns matching identifiers, with their placeholders.
+    mask_patterns: Vec<(Regex, String)>,
+    /// Patterns matching database migration file paths.
+    migration_patterns: GlobSet,
+    /// Patterns matching generated file paths.
+    generated_patterns: GlobSet,
+    /// Patterns matching the paths of the files to analyze, if any.
+    include_paths: Option<GlobSet>,
+    /// Patterns matching the paths of the files to ignore.
+    exclude_paths: GlobSet,
+    /// Pattern matching the author name or email of bot commits, unless bots are not filtered.
+    bot_pattern: Option<Regex>,
+    /// Names of the renamed output columns, by default name.
+    column_names: HashMap<String, String>,
+    /// Set of normalized messages of the saved commits.
+    saved_message_keys: HashSet<String>,
+    /// Set of hashes of the message and changes of the records saved, existing ones included.
+    saved_record_hashes: HashSet<u64>,
+    /// Depth of each commit reachable from the ref.
+    commit_depths: HashMap<Oid, usize>,
+    /// Number of commits per author.
+    author_commit_counts: HashMap<String, usize>,
+    /// Set of authors whose commits can be saved, if limited.
+    selected_authors: Option<HashSet<String>>,
+    /// Extracted data from commits, kept in memory unless written to the open output files.
+    records: Vec<Record>,
+    /// Set of commit ids processed.
+    processed_commit_ids: HashSet<Oid>,
+    /// Number of commits scanned by the history walk.
+    nb_commits_scanned: usize,
+    /// Number of commits saved in the dataset.
+    nb_commits_saved: usize,
+    /// Number of commits saved per calendar day.
+    nb_commits_saved_per_day: HashMap<i64, usize>,
+    /// Number of commits allowed per target file extension.
+    extension_allocations: Option<HashMap<OsString, usize>>,
+    /// Number of commits saved per target file extension.
+    nb_commits_saved_per_extension: HashMap<OsString, usize>,
+    /// Number of commits skipped per reason.
+    nb_commits_skipped: RefCell<BTreeMap<&'static str, usize>>,
+    /// Files the saved records are written to, if open.
+    dataset_writer: RefCell<Option<DatasetWriter>>,
+    /// Thread pool computing the diffs of commits ahead, unless single-threaded.
+    diff_pool: Option<ThreadPool>,
+    /// Changes of the commits computed ahead, by commit id.
+    prefetched_changes: RefCell<HashMap<Oid, Changes>>,
+    /// Last commit visited by the history walk of an interrupted run, to resume after.
+    checkpoint_oid: Option<Oid>,
+    /// Spinner of the commits scanned by the history walk.
+    scan_progress: ProgressBar,
+    /// Progress bar of the commits saved.
+    progress_bar: ProgressBar,
+}
+
+impl Extractor {
+    /// Open the Git repository, retrying with exponential backoff on failure.
+    fn open_repository(path: &Path, retries: u32) -> Result<Repository> {
+        let mut attempt = 0;
+        loop {
+            match Repository::open(path) {
+                Ok(git_repo) => return Ok(git_repo),
+                Err(e) if attempt < retries => {
+                    let delay = OPEN_RETRY_DELAY * 2_u32.saturating_pow(attempt);
+                    attempt += 1;
+                    info!("Retry opening the Git repository in {delay:?}, attempt {attempt}/{retries} ({e})");
+                    thread::sleep(delay);
+                }
+                Err(e) => return Err(anyhow!("failed to open the Git repository ({e})")),
+            }
+        }
+    }
+
+    /// Create a new `Extractor` instance with the given configuration.
+    pub fn new(config: Config) -> Result<Extractor> {
+        // Open the Git repository
+        let repository = config
+            .repository
+            .as_deref()
+            .ok_or_else(|| anyhow!("no Git repository to extract from"))?;
+        let git_repo = Self::open_repository(repository, config.open_retries)?;
+
+        // Check if the Git repository is a shallow clone
+        if git_repo.is_shallow() {
+            if !config.allow_shallow {
+                return Err(anyhow!(
+                    "the Git repository is a shallow clone, its history is truncated (use --allow-shallow to extract anyway)"
+                ));
+            }
+            eprintln!(
+                "Warning: the Git repository is a shallow clone, its history is truncated and results will be limited"
+            );
+        }
+
+        if let Some(config_file) = &config.config_file {
+            info!(
+                "Options completed with those of the config file '{}'",
+                config_file.display()
+            );
+        }
+
+        // Check if a ref or an order is given with the working directory changes, which walk no
+        // history
+        // (a defaulted option always conflicts in structopt, so it is checked here)
+        if config.workdir && config.git_ref != "HEAD" {
+            return Err(anyhow!("--ref cannot be used with --workdir"));
+        }
+        if config.workdir && config.sort != "time" {
+            return Err(anyhow!("--sort cannot be used with --workdir"));
+        }
+
+        // Check if the ref is overridden by the range
+        if config.range.is_some() && config.git_ref != "HEAD" {
+            eprintln!("Warning: --ref is ignored as the commits of --range are walked instead");
+        }
+
+        // Create thread pool computing diffs, with as many threads as logical CPUs by default
+        let diff_pool = match config.jobs {
+            Some(0) => return Err(anyhow!("the number of jobs must be at least 1")),
+            Some(1) => None,
+            jobs => Some(
+                ThreadPoolBuilder::new()
+                    .num_threads(jobs.unwrap_or(0))
+                    .build()
+                    .map_err(|e| anyhow!("failed to create the thread pool ({e})"))?,
+            ),
+        };
+
+        // Check date range
+        if let (Some(since), Some(until)) = (config.since, config.until) {
+            if since > until {
+                return Err(anyhow!("the --since date is after the --until date"));
+            }
+        }
+
+        // Check options dropping or rewriting lines or headers of the commit changes, whose patches
+        // would no longer apply
+        // (a defaulted option always conflicts in structopt, so it is checked here)
+        if config.patch_format == "applicable" {
+            let option = if config.added_only {
+                Some("--added-only")
+            } else if config.removed_only {
+                Some("--removed-only")
+            } else if config.content_only {
+                Some("--content-only")
+            } else if config.trim_changes_trailing {
+                Some("--trim-changes-trailing")
+            } else if config.drop_whitespace_only_lines {
+                Some("--drop-whitespace-only-lines")
+            } else if config.truncate_changes {
+                Some("--truncate-changes")
+            } else if config.mask_identifiers_in_changes {
+                Some("--mask-identifiers-in-changes")
+            } else {
+                None
+            };
+            if let Some(option) = option {
+                return Err(anyhow!(
+                    "{option} cannot be used with --patch-format applicable"
+                ));
+            }
+        }
+
+        // Check options writing beside the output, which the standard output has not
+        if is_stdout_output(config.output.as_deref()) {
+            let option = if config.rotate_size.is_some() {
+                Some("--rotate-size")
+            } else if config.partitions.is_some() {
+                Some("--partitions")
+            } else if config.checkpoint.is_some() {
+                Some("--checkpoint")
+            } else if config.append_lock {
+                Some("--append-lock")
+            } else {
+                None
+            };
+            if let Some(option) = option {
+                return Err(anyhow!("{option} requires an output file (use --output)"));
+            }
+        }
+
+        // Check bounds of the number of files changed
+        if let (Some(min_files), Some(max_files)) = (config.min_files, config.max_files) {
+            if min_files > max_files {
+                return Err(anyhow!(
+                    "the --min-files number is greater than the --max-files number"
+                ));
+            }
+        }
+
+        // Check checkpoint interval
+        if config.checkpoint_interval == 0 {
+            return Err(anyhow!("the checkpoint interval must be at least 1"));
+        }
+
+        // Check if commits of a resume state or a checkpoint are given to verify
+        if config.resume_verify && config.resume_state.is_none() && config.checkpoint.is_none() {
+            return Err(anyhow!(
+                "--resume-verify requires --resume-state or --checkpoint"
+            ));
+        }
+
+        // Check number of partitions
+        if config.partitions == Some(0) {
+            return Err(anyhow!("the number of partitions must be at least 1"));
+        }
+
+        // Check if the maximum changes length holds the truncation marker
+        if config.truncate_changes {
+            let marker_len = truncation_marker_len(&config.changes_len_unit);
+            if config
+                .changes_len_max
+                .is_some_and(|changes_len_max| changes_len_max < marker_len)
+            {
+                return Err(anyhow!(
+                    "--changes-len-max must be at least {marker_len} {} with --truncate-changes, the length of the truncation marker",
+                    config.changes_len_unit
+                ));
+            }
+        }
+
+        // Check split fraction
+        if let Some(split) = config.split {
+            if !(0.0..=1.0).contains(&split) {
+                return Err(anyhow!("split must be between 0.0 and 1.0 (got {split})"));
+            }
+        }
+
+        // Check sample fraction
+        if let Some(sample) = config.sample {
+            if !(0.0..=1.0).contains(&sample) {
+                return Err(anyhow!("sample must be between 0.0 and 1.0 (got {sample})"));
+            }
+        }
+
+        // Resolve targeted languages
+        let mut languages = config
+            .languages
+            .iter()
+            .map(|name| {
+                LANGUAGES
+                    .iter()
+                    .find(|language| language.name.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| {
+                        let names: Vec<&str> =
+                            LANGUAGES.iter().map(|language| language.name).collect();
+                        anyhow!(
+                            "unknown language '{name}' (expected one of: {})",
+                            names.join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<Vec<&Language>>>()?;
+
+        // Detect the most common languages
+        if config.auto_languages {
+            let detected_languages =
+                Self::detect_languages(&git_repo, config.auto_languages_count)?;
+            let names: Vec<String> = detected_languages
+                .iter()
+                .map(|(language, nb_files)| format!("{} ({nb_files})", language.name))
+                .collect();
+            let mut summary = get_summary_writer(config.output.as_deref());
+            writeln!(summary, "Detected languages (files): {}", names.join(", "))?;
+            languages.extend(
+                detected_languages
+                    .into_iter()
+                    .map(|(language, _)| language)
+                    .filter(|language| {
+                        !config
+                            .languages
+                            .iter()
+                            .any(|name| name.eq_ignore_ascii_case(language.name))
+                    }),
+            );
+        }
+
+        // Convert file extensions to a HashSet, in lowercase and without leading dot
+        let file_extensions = config
+            .extensions
+            .iter()
+            .map(String::as_str)
+            .chain(
+                languages
+                    .iter()
+                    .flat_map(|language| language.extensions.iter().copied()),
+            )
+            .map(|ext| OsString::from(ext.trim_start_matches('.').to_lowercase()))
+            .collect();
+
+        // Convert file names to a HashSet, in lowercase
+        let file_names = config
+            .include_filename
+            .iter()
+            .map(|name| OsString::from(name.to_lowercase()))
+            .collect();
+
+        // Resolve the baseline tree
+        let baseline_tree_id = match &config.baseline_ref {
+            Some(baseline_ref) => Some(
+                git_repo
+                    .revparse_single(baseline_ref)
+                    .and_then(|object| object.peel_to_tree())
+                    .map(|tree| tree.id())
+                    .map_err(|e| anyhow!("failed to resolve baseline '{baseline_ref}' ({e})"))?,
+            ),
+            None => None,
+        };
+
+        // Load commit ids to exclude
+        let mut excluded_commits = config.exclude_commits.clone();
+        if let Some(path) = &config.exclude_commits_file {
+            let content = fs::read_to_string(path)
+                .map_err(|e| anyhow!("failed to read the exclude commits file ({e})"))?;
+            excluded_commits.extend(
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+        let excluded_commit_ids = excluded_commits
+            .iter()
+            .map(|id| {
+                Oid::from_str(id).map_err(|e| anyhow!("invalid commit id to exclude '{id}' ({e})"))
+            })
+            .collect::<Result<HashSet<Oid>>>()?;
+
+        // Resume after the checkpoint of an interrupted run, before loading the resume state
+        let checkpoint = Self::restore_checkpoint(&config)?;
+        let checkpoint_oid = checkpoint.as_ref().map(|checkpoint| checkpoint.commit_oid);
+        if let Some(checkpoint_oid) = checkpoint_oid {
+            git_repo.find_commit(checkpoint_oid).map_err(|e| {
+                anyhow!("failed to find the checkpoint commit {checkpoint_oid}, the history may have been rewritten (delete the checkpoint file to start over) ({e})")
+            })?;
+            // Check before walking, as the walk would only tell once it is over
+            if !Self::is_walk_reachable(&git_repo, &config, checkpoint_oid)? {
+                return Err(anyhow!(
+                    "the checkpoint commit {checkpoint_oid} is not reachable from the ref, the history may have been rewritten (delete the checkpoint file to start over)"
+                ));
+            }
+        }
+
+        // Load commit ids saved in previous runs
+        let resumed_commit_ids = match config.resume_state.as_ref().filter(|path| path.exists()) {
+            Some(path) => Self::read_resume_state(path)?,
+            None => HashSet::new(),
+        };
+
+        // Compile commit message template patterns
+        let template_patterns = if config.template_patterns.is_empty() {
+            DEFAULT_TEMPLATE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect()
+        } else {
+            config.template_patterns.clone()
+        };
+        let template_patterns = template_patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .multi_line(true)
+                    .build()
+                    .map_err(|e| anyhow!("invalid template pattern '{pattern}' ({e})"))
+            })
+            .collect::<Result<Vec<Regex>>>()?;
+
+        // Compile commit message include and exclude patterns
+        let message_include_patterns = config
+            .message_includes
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| anyhow!("invalid message include pattern '{pattern}' ({e})"))
+            })
+            .collect::<Result<Vec<Regex>>>()?;
+        let default_message_excludes: &[&str] = if config.no_default_message_exclude {
+            &[]
+        } else {
+            DEFAULT_MESSAGE_EXCLUDE_PATTERNS
+        };
+        let message_exclude_patterns = default_message_excludes
+            .iter()
+            .copied()
+            .chain(config.message_excludes.iter().map(String::as_str))
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| anyhow!("invalid message exclude pattern '{pattern}' ({e})"))
+            })
+            .collect::<Result<Vec<Regex>>>()?;
+
+        // Compile identifier patterns
+        let mask_patterns = if config.mask_patterns.is_empty() {
+            DEFAULT_MASK_PATTERNS
+                .iter()
+                .map(|(pattern, placeholder)| (pattern.to_string(), placeholder.to_string()))
+                .collect()
+        } else {
+            config
+                .mask_patterns
+                .iter()
+                .map(|mask_pattern| {
+                    mask_pattern
+                        .rsplit_once('=')
+                        .map(|(pattern, placeholder)| (pattern.to_string(), placeholder.to_string()))
+                        .ok_or_else(|| {
+                            anyhow!("invalid mask pattern '{mask_pattern}' (expected REGEX=PLACEHOLDER)")
+                        })
+                })
+                .collect::<Result<Vec<(String, String)>>>()?
+        };
+        let mask_patterns = mask_patterns
+            .into_iter()
+            .map(|(pattern, placeholder)| {
+                Regex::new(&pattern)
+                    .map(|regex| (regex, placeholder))
+                    .map_err(|e| anyhow!("invalid mask pattern '{pattern}' ({e})"))
+            })
+            .collect::<Result<Vec<(Regex, String)>>>()?;
+
+        // Compile bot pattern
+        let bot_pattern = if config.no_bot_filter {
+            None
+        } else {
+            Some(
+                Regex::new(&config.bot_pattern)
+                    .map_err(|e| anyhow!("invalid bot pattern '{}' ({e})", config.bot_pattern))?,
+            )
+        };
+
+        // Compile database migration file patterns
+        let mut migration_patterns = GlobSetBuilder::new();
+        if config.migration_patterns.is_empty() {
+            for pattern in DEFAULT_MIGRATION_PATTERNS {
+                migration_patterns.add(Glob::new(pattern)?);
+            }
+        } else {
+            for pattern in &config.migration_patterns {
+                migration_patterns.add(
+                    Glob::new(pattern)
+                        .map_err(|e| anyhow!("invalid migration pattern '{pattern}' ({e})"))?,
+                );
+            }
+        }
+        let migration_patterns = migration_patterns
+            .build()
+            .map_err(|e| anyhow!("failed to build migration patterns ({e})"))?;
+
+        // Compile generated file patterns
+        let mut generated_patterns = GlobSetBuilder::new();
+        for pattern in DEFAULT_GENERATED_PATTERNS {
+            generated_patterns.add(Glob::new(pattern)?);
+        }
+        for pattern in &config.generated_pattern {
+            generated_patterns.add(
+                Glob::new(pattern)
+                    .map_err(|e| anyhow!("invalid generated pattern '{pattern}' ({e})"))?,
+            );
+        }
+        let generated_patterns = generated_patterns
+            .build()
+            .map_err(|e| anyhow!("failed to build generated patterns ({e})"))?;
+
+        // Compile file path patterns
+        let build_path_patterns = |patterns: &[String]| {
+            let mut path_patterns = GlobSetBuilder::new();
+            for pattern in patterns {
+                path_patterns.add(
+                    Glob::new(pattern)
+                        .map_err(|e| anyhow!("invalid path pattern '{pattern}' ({e})"))?,
+                );
+            }
+            path_patterns
+                .build()
+                .map_err(|e| anyhow!("failed to build path patterns ({e})"))
+        };
+        let include_paths = if config.include_paths.is_empty() {
+            None
+        } else {
+            Some(build_path_patterns(&config.include_paths)?)
+        };
+        let exclude_paths = build_path_patterns(&config.exclude_paths)?;
+
+        // Get names of the renamed output columns
+        let mut column_names = config
+            .rename_columns
+            .iter()
+            .map(|rename| {
+                rename
+                    .split_once('=')
+                    .map(|(name, new_name)| (name.to_string(), new_name.to_string()))
+                    .ok_or_else(|| anyhow!("invalid column renaming '{rename}'"))
+            })
+            .collect::<Result<HashMap<String, String>>>()?;
+        for (name, new_name) in [
+            ("commit_message", &config.message_column),
+            ("commit_changes", &config.changes_column),
+        ] {
+            if new_name != name {
+                column_names.insert(name.to_string(), new_name.clone());
+            }
+        }
+
+        // Hold number of commits per author
+        let author_commit_counts = HashMap::new();
+
+        // Hold extracted data from commits
+        let records = Vec::new();
+
+        // Hold processed commit ids
+        let processed_commit_ids = HashSet::new();
+
+        // Hold number of commits saved, including those saved before the checkpoint
+        let nb_commits_saved = checkpoint.map_or(0, |checkpoint| checkpoint.nb_commits_saved);
+        let nb_commits_saved_per_day = HashMap::new();
+        let nb_commits_saved_per_extension = HashMap::new();
+
+        // Create progress bar of the commits saved, below a spinner of the commits scanned
+        let (scan_progress, progress_bar) = if config.show_progress {
+            let multi_progress = MultiProgress::new();
+            (
+                multi_progress.add(ProgressBar::new_spinner()),
+                multi_progress.add(ProgressBar::new(config.size as u64)),
+            )
+        } else {
+            (ProgressBar::hidden(), ProgressBar::hidden())
+        };
+
+        // Configure progress bars
+        progress_bar.set_style(
+            ProgressStyle::with_template(&config.progress_template)
+                .map_err(|e| anyhow!("invalid progress bar template ({e})"))?
+                .progress_chars("#>-"),
+        );
+        scan_progress.set_style(
+            ProgressStyle::with_template(SCAN_PROGRESS_TEMPLATE)
+                .map_err(|e| anyhow!("invalid progress bar template ({e})"))?,
+        );
+
+        let extractor = Extractor {
+            config,
+            git_repo,
+            file_extensions,
+            file_names,
+            languages,
+            baseline_tree_id,
+            excluded_commit_ids,
+            resumed_commit_ids,
+            reverted_commit_ids: HashSet::new(),
+            template_patterns,
+            message_include_patterns,
+            message_exclude_patterns,
+            mask_patterns,
+            migration_patterns,
+            generated_patterns,
+            include_paths,
+            exclude_paths,
+            bot_pattern,
+            column_names,
+            saved_message_keys: HashSet::new(),
+            saved_record_hashes: HashSet::new(),
+            commit_depths: HashMap::new(),
+            author_commit_counts,
+            selected_authors: None,
+            records,
+            processed_commit_ids,
+            nb_commits_scanned: 0,
+            nb_commits_saved,
+            nb_commits_saved_per_day,
+            extension_allocations: None,
+            nb_commits_saved_per_extension,
+            nb_commits_skipped: RefCell::new(BTreeMap::new()),
+            dataset_writer: RefCell::new(None),
+            diff_pool,
+            prefetched_changes: RefCell::new(HashMap::new()),
+            checkpoint_oid,
+            scan_progress,
+            progress_bar,
+        };
+
+        Ok(extractor)
+    }
+
+    /// Read the commit ids saved in previous runs from a given resume state file.
+    fn read_resume_state(path: &Path) -> Result<HashSet<Oid>> {
+        let state_file =
+            File::open(path).map_err(|e| anyhow!("failed to open the resume state file ({e})"))?;
+        let mut resumed_commit_ids = HashSet::new();
+        for line in BufReader::new(state_file).lines() {
+            let line = line.map_err(|e| anyhow!("failed to read the resume state file ({e})"))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let commit_oid = Oid::from_str(line.trim()).map_err(|e| {
+                anyhow!("invalid commit id in the resume state file '{line}' ({e})")
+            })?;
+            resumed_commit_ids.insert(commit_oid);
+        }
+        Ok(resumed_commit_ids)
+    }
+
+    /// Count a commit skipped for the given reason.
+    fn count_skip(&self, reason: &'static str) {
+        *self
+            .nb_commits_skipped
+            .borrow_mut()
+            .entry(reason)
+            .or_insert(0) += 1;
+    }
+
+    /// Get message of a given Git commit.
+    /// If the commit message header does not match the required length, return None.
+    fn get_commit_message(&self, commit: &Commit) -> Option<String> {
+        // Get first line (commit summary)
+        let first_line = commit.message()?.lines().next()?;
+
+        // Check first line length
+        let message_len = measure_len(first_line, &self.config.message_len_unit);
+        if message_len < self.config.message_len_min || message_len > self.config.message_len_max {
+            return None;
+        }
+
+        Some(first_line.to_string())
+    }
+
+    /// Get the body of the commit message of a given Git commit, without the blank lines
+    /// separating it from the summary.
+    fn get_commit_body(commit: &Commit) -> String {
+        commit
+            .message()
+            .and_then(|message| message.split_once('\n'))
+            .map(|(_, body)| body.trim_start_matches(['\r', '\n']).trim_end().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Check if a given commit message is a single token ending with a target extension or the
+    /// extension of a known language, e.g. `main.py`.
+    fn is_filename_message(&self, message: &str) -> bool {
+        let message = message.trim();
+        if message.contains(char::is_whitespace) {
+            return false;
+        }
+        get_target_extension(&self.file_extensions, &self.file_names, Path::new(message)).is_some()
+            || Path::new(message)
+                .extension()
+                .map(|ext| {
+                    LANGUAGES
+                        .iter()
+                        .any(|language| language.extensions.iter().any(|known| ext == *known))
+                })
+                .unwrap_or(false)
+    }
+
+    /// Normalize a commit message for deduplication into its lowercase words without punctuation,
+    /// each word being stemmed with the `stem` normalization.
+    fn normalize_message(&self, message: &str) -> String {
+        let stem = self.config.dedup_normalization == "stem";
+        message
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| if stem { Self::stem_word(word) } else { word })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Strip a common English inflection suffix from a word, keeping a stem of at least 3
+    /// characters, e.g. `fixed`, `fixes` and `fixing` become `fix`.
+    fn stem_word(word: &str) -> &str {
+        if word.ends_with("ss") {
+            return word;
+        }
+        ["ing", "ed", "es", "s"]
+            .iter()
+            .filter_map(|suffix| word.strip_suffix(suffix))
+            .find(|stem| stem.chars().count() >= 3)
+            .unwrap_or(word)
+    }
+
+    /// Detect the most common languages of the files at HEAD, with their number of files.
+    fn detect_languages(
+        git_repo: &Repository,
+        count: usize,
+    ) -> Result<Vec<(&'static Language, usize)>> {
+        let head_tree = git_repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .map_err(|e| anyhow!("failed to read the HEAD tree ({e})"))?;
+
+        // Count files per language
+        let mut nb_files_per_language: HashMap<&str, usize> = HashMap::new();
+        head_tree.walk(TreeWalkMode::PreOrder, |_, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                let extension = entry
+                    .name()
+                    .and_then(|name| Path::new(name).extension())
+                    .and_then(|ext| ext.to_str());
+                if let Some(language) = extension.and_then(|ext| {
+                    LANGUAGES
+                        .iter()
+                        .find(|language| language.extensions.contains(&ext))
+                }) {
+                    *nb_files_per_language.entry(language.name).or_insert(0) += 1;
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        // Select the languages with the most files
+        let mut languages: Vec<(&Language, usize)> = LANGUAGES
+            .iter()
+            .filter_map(|language| {
+                nb_files_per_language
+                    .get(language.name)
+                    .map(|nb_files| (language, *nb_files))
+            })
+            .collect();
+        languages.sort_by(|(_, a), (_, b)| b.cmp(a));
+        languages.truncate(count);
+
+        if languages.is_empty() {
+            return Err(anyhow!("failed to detect any language at HEAD"));
+        }
+
+        Ok(languages)
+    }
+
+    /// Get the key identifying the author of a given Git commit.
+    fn get_author_key(commit: &Commit) -> String {
+        let author = commit.author();
+        match author.email() {
+            Some(email) if !email.is_empty() => email.to_lowercase(),
+            _ => author.name().unwrap_or_default().to_string(),
+        }
+    }
+
+    /// Create a revwalk to iterate on the commits of the range, or reachable from the ref.
+    fn create_revwalk<'repo>(
+        git_repo: &'repo Repository,
+        config: &Config,
+    ) -> Result<Revwalk<'repo>> {
+        let mut revwalk = git_repo.revwalk()?;
+        let mut sorting = match config.sort.as_str() {
+            "topological" => Sort::TOPOLOGICAL,
+            "none" => Sort::NONE,
+            _ => Sort::TIME,
+        };
+        if config.reverse {
+            sorting |= Sort::REVERSE;
+        }
+        revwalk.set_sorting(sorting)?;
+        if config.first_parent {
+            revwalk.simplify_first_parent()?;
+        }
+
+        let (tips, hidden) = Self::get_walk_tips(git_repo, config)?;
+        for tip in tips {
+            revwalk.push(tip)?;
+        }
+        for hidden in hidden {
+            revwalk.hide(hidden)?;
+        }
+        Ok(revwalk)
+    }
+
+    /// Get the commits the history walk starts from, and those whose history it hides.
+    fn get_walk_tips(git_repo: &Repository, config: &Config) -> Result<(Vec<Oid>, Vec<Oid>)> {
+        if let Some(range) = &config.range {
+            let revspec = git_repo
+                .revparse(range)
+                .map_err(|e| anyhow!("failed to resolve the range {range} ({e})"))?;
+            let (from, to) = match (revspec.from(), revspec.to()) {
+                (Some(from), Some(to)) if revspec.mode().contains(RevparseMode::RANGE) => {
+                    (from.peel_to_commit()?.id(), to.peel_to_commit()?.id())
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "failed to resolve the range {range} (expected `A..B` or `A...B`)"
+                    ))
+                }
+            };
+
+            if revspec.mode().contains(RevparseMode::MERGE_BASE) {
+                // Commits reachable from either side but not from both
+                let merge_bases = git_repo.merge_bases(from, to)?.iter().copied().collect();
+                return Ok((vec![to, from], merge_bases));
+            }
+            // Commits reachable from the end but not from the start
+            return Ok((vec![to], vec![from]));
+        }
+
+        let git_ref = &config.git_ref;
+        let commit = git_repo
+            .revparse_single(git_ref)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| anyhow!("failed to resolve the ref {git_ref} ({e})"))?;
+        Ok((vec![commit.id()], Vec::new()))
+    }
+
+    /// Check if a given commit is reachable from the commits the history walk starts from, and
+    /// not hidden by the others, without walking the history.
+    fn is_walk_reachable(git_repo: &Repository, config: &Config, commit_oid: Oid) -> Result<bool> {
+        let is_reachable_from = |tip: Oid| -> Result<bool> {
+            Ok(tip == commit_oid || git_repo.graph_descendant_of(tip, commit_oid)?)
+        };
+        let (tips, hidden) = Self::get_walk_tips(git_repo, config)?;
+        for hidden in hidden {
+            if is_reachable_from(hidden)? {
+                return Ok(false);
+            }
+        }
+        for tip in tips {
+            if is_reachable_from(tip)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Get the commits to process for a given Git commit.
+    /// A merge commit is skipped, processed itself, or replaced by its parents, depending on the
+    /// merge strategy.
+    fn get_commits_to_process<'repo>(&self, commit: Commit<'repo>) -> Vec<Commit<'repo>> {
+        if commit.parent_count() <= 1 {
+            return vec![commit];
+        }
+        match self.config.merge_strategy.as_str() {
+            // The merge commit is diffed against its first parent
+            "first-parent" => vec![commit],
+            "parents" => commit.parents().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Count the number of commits of each author reachable from the ref.
+    /// Return the authors in the order they are first encountered.
+    fn count_author_commits(&mut self) -> Result<Vec<String>> {
+        let revwalk = Self::create_revwalk(&self.git_repo, &self.config)?;
+        let mut authors = Vec::new();
+
+        for commit_oid in revwalk {
+            let commit_oid = match commit_oid {
+                Ok(commit_oid) => commit_oid,
+                Err(_) if self.git_repo.is_shallow() => break,
+                Err(e) => return Err(e.into()),
+            };
+            let commit = self.git_repo.find_commit(commit_oid)?;
+            let author_key = Self::get_author_key(&commit);
+            let author_commits = self
+                .author_commit_counts
+                .entry(author_key.clone())
+                .or_insert(0);
+            if *author_commits == 0 {
+                authors.push(author_key);
+            }
+            *author_commits += 1;
+        }
+
+        Ok(authors)
+    }
+
+    /// Collect the ids of the commits reverted by commits reachable from the ref, as referenced by
+    /// the `This reverts commit <id>.` line of their messages.
+    fn collect_reverted_commits(&mut self) -> Result<()> {
+        let revert_pattern = Regex::new(r"This reverts commit ([0-9a-f]{40})")?;
+        let revwalk = Self::create_revwalk(&self.git_repo, &self.config)?;
+
+        for commit_oid in revwalk {
+            let commit_oid = match commit_oid {
+                Ok(commit_oid) => commit_oid,
+                Err(_) if self.git_repo.is_shallow() => break,
+                Err(e) => return Err(e.into()),
+            };
+            let commit = self.git_repo.find_commit(commit_oid)?;
+            let message = commit.message().unwrap_or_default();
+            for captures in revert_pattern.captures_iter(message) {
+                self.reverted_commit_ids
+                    .insert(Oid::from_str(&captures[1])?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the commits saved in previous runs still exist and are reachable from the ref, as
+    /// they may not be after a force-push or a rebase.
+    fn verify_resumed_commits(&self) -> Result<()> {
+        let mut unreachable_commit_ids = self.resumed_commit_ids.clone();
+        let revwalk = Self::create_revwalk(&self.git_repo, &self.config)?;
+
+        for commit_oid in revwalk {
+            if unreachable_commit_ids.is_empty() {
+                break;
+            }
+            let commit_oid = match commit_oid {
+                Ok(commit_oid) => commit_oid,
+                Err(_) if self.git_repo.is_shallow() => break,
+                Err(e) => return Err(e.into()),
+            };
+            unreachable_commit_ids.remove(&commit_oid);
+        }
+
+        if let Some(commit_oid) = unreachable_commit_ids.iter().next() {
+            let reason = match self.git_repo.find_commit(*commit_oid) {
+                Ok(_) => "is not reachable from the ref",
+                Err(_) => "does not exist",
+            };
+            return Err(anyhow!(
+                "commit #{commit_oid} of the resume state {reason}, the history may have been rewritten (remove the resume state file and the output to start fresh)"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Compute the depth of each commit reachable from the ref, a root commit having a depth of 0
+    /// and other commits one more than their deepest parent.
+    fn compute_commit_depths(&mut self) -> Result<()> {
+        let mut revwalk = Self::create_revwalk(&self.git_repo, &self.config)?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+        for commit_oid in revwalk {
+            let commit_oid = match commit_oid {
+                Ok(commit_oid) => commit_oid,
+                Err(_) if self.git_repo.is_shallow() => break,
+                Err(e) => return Err(e.into()),
+            };
+            let commit = self.git_repo.find_commit(commit_oid)?;
+            // Parents beyond the boundary of a shallow clone are missing
+            let depth = commit
+                .parent_ids()
+                .filter_map(|parent_oid| self.commit_depths.get(&parent_oid))
+                .max()
+                .map_or(0, |depth| depth + 1);
+            self.commit_depths.insert(commit_oid, depth);
+        }
+
+        Ok(())
+    }
+
+    /// Select at most `max_authors` authors among the given ones, the most prolific first or
+    /// in the order they are encountered, ties being broken by encounter order.
+    fn select_authors(&self, mut authors: Vec<String>, max_authors: usize) -> HashSet<String> {
+        if self.config.authors_selection == "frequency" {
+            authors.sort_by_key(|author| std::cmp::Reverse(self.author_commit_counts[author]));
+        }
+        authors.into_iter().take(max_authors).collect()
+    }
+
+    /// Get the calendar day of a given Git commit, as a number of days since the epoch in the
+    /// timezone of the committer.
+    fn get_commit_day(commit: &Commit) -> i64 {
+        let time = commit.time();
+        (time.seconds() + i64::from(time.offset_minutes()) * 60).div_euclid(86400)
+    }
+
+    /// Allocate the dataset size across target file extensions, in proportion to their number
+    /// of qualifying commits found by a first pass over the history.
+    /// Allocations are rounded down, the remainder going to the largest fractional parts.
+    fn allocate_extensions(&self) -> Result<HashMap<OsString, usize>> {
+        // Count qualifying commits per extension
+        let mut nb_commits_per_extension: HashMap<OsString, usize> = HashMap::new();
+        let mut counted_commit_ids = HashSet::new();
+        for commit_oid in Self::create_revwalk(&self.git_repo, &self.config)? {
+            let commit_oid = match commit_oid {
+                Ok(commit_oid) => commit_oid,
+                Err(_) if self.git_repo.is_shallow() => break,
+                Err(e) => return Err(e.into()),
+            };
+            let commit = self.git_repo.find_commit(commit_oid)?;
+            for commit in self.get_commits_to_process(commit) {
+                if !counted_commit_ids.insert(commit.id()) {
+                    continue;
+                }
+                for record in self.process_commit(&commit)? {
+                    *nb_commits_per_extension
+                        .entry(record.extension)
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Forget the commits skipped by the prepass, counted again by the walk
+        self.nb_commits_skipped.borrow_mut().clear();
+
+        // Keep all qualifying commits if there are not enough of them
+        let nb_commits: usize = nb_commits_per_extension.values().sum();
+        if nb_commits <= self.config.size {
+            return Ok(nb_commits_per_extension);
+        }
+
+        // Allocate the dataset size in proportion to the number of qualifying commits
+        let mut allocations: Vec<(OsString, usize, usize)> = nb_commits_per_extension
+            .into_iter()
+            .map(|(ext, count)| {
+                let share = count * self.config.size;
+                (ext, share / nb_commits, share % nb_commits)
+            })
+            .collect();
+        let remainder = self.config.size - allocations.iter().map(|(_, n, _)| n).sum::<usize>();
+        allocations.sort_by(|(a_ext, _, a_rem), (b_ext, _, b_rem)| {
+            b_rem.cmp(a_rem).then_with(|| a_ext.cmp(b_ext))
+        });
+        for (_, allocation, _) in allocations.iter_mut().take(remainder) {
+            *allocation += 1;
+        }
+
+        Ok(allocations
+            .into_iter()
+            .map(|(ext, allocation, _)| (ext, allocation))
+            .collect())
+    }
+
+    /// Detect the language of a given commit message, as an ISO 639-3 code.
+    /// If the language cannot be reliably detected (e.g. the message is too short), return `und`.
+    fn detect_message_lang(commit_message: &str) -> String {
+        whatlang::detect(commit_message)
+            .filter(|info| info.is_reliable())
+            .map(|info| info.lang().code().to_string())
+            .unwrap_or_else(|| "und".to_string())
+    }
+
+    /// Check if the message of a given Git commit contains template artifacts.
+    fn is_templated_message(&self, commit: &Commit) -> bool {
+        commit
+            .message()
+            .map(|message| {
+                self.template_patterns
+                    .iter()
+                    .any(|pattern| pattern.is_match(message))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Replace identifiers in a given text with their placeholders.
+    /// Names shaped like ticket keys but naming encodings or standards, such as `UTF-8`, are left
+    /// as they are.
+    fn mask_identifiers(&self, text: &str) -> String {
+        self.mask_patterns
+            .iter()
+            .fold(text.to_string(), |text, (pattern, placeholder)| {
+                pattern
+                    .replace_all(&text, |captures: &Captures| {
+                        let identifier = &captures[0];
+                        let is_non_ticket = identifier
+                            .split_once('-')
+                            .is_some_and(|(key, _)| NON_TICKET_KEYS.contains(&key));
+                        if is_non_ticket {
+                            identifier.to_string()
+                        } else {
+                            placeholder.clone()
+                        }
+                    })
+                    .into_owned()
+            })
+    }
+
+    /// Get the analyzer of the diffs of commits.
+    fn get_diff_analyzer(&self) -> DiffAnalyzer<'_> {
+        DiffAnalyzer {
+            config: &self.config,
+            file_extensions: &self.file_extensions,
+            file_names: &self.file_names,
+            migration_patterns: &self.migration_patterns,
+            generated_patterns: &self.generated_patterns,
+            include_paths: self.include_paths.as_ref(),
+            exclude_paths: &self.exclude_paths,
+        }
+    }
+
+    /// Get changes of a given Git commit.
+    /// If files with others extensions than the target extensions contain changes, return None.
+    fn get_commit_changes(&self, commit_tree: &Tree, parent_tree: &Tree) -> Result<Changes> {
+        self.get_diff_analyzer()
+            .get_tree_changes(&self.git_repo, commit_tree, parent_tree)
+    }
+
+    /// Get uncommitted changes of the working directory, staged or not, against HEAD.
+    fn get_workdir_changes(&self) -> Result<Changes> {
+        let head_tree = self
+            .git_repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .map_err(|e| anyhow!("failed to fetch the HEAD tree ({e})"))?;
+
+        // Create a diff representing the difference between the HEAD tree and the working directory
+        let diff_output = self
+            .git_repo
+            .diff_tree_to_workdir_with_index(
+                Some(&head_tree),
+                Some(&mut self.get_diff_analyzer().get_diff_options()),
+            )
+            .map_err(|e| anyhow!("failed to create diff ({e})"))?;
+
+        self.get_diff_analyzer().get_diff_changes(diff_output)
+    }
+
+    /// Get the required commit changes length bounds for the given changed file extensions.
+    /// Explicit bounds take precedence, then the least restrictive bounds of the targeted
+    /// languages owning the extensions, then the default bounds.
+    fn get_changes_len_bounds(&self, extensions: &HashSet<OsString>) -> (usize, usize) {
+        let languages: Vec<&Language> = self
+            .languages
+            .iter()
+            .copied()
+            .filter(|language| {
+                language
+                    .extensions
+                    .iter()
+                    .any(|ext| extensions.contains(&OsString::from(ext)))
+            })
+            .collect();
+
+        let changes_len_min = self.config.changes_len_min.unwrap_or_else(|| {
+            languages
+                .iter()
+                .map(|language| language.changes_len_min)
+                .min()
+                .unwrap_or(DEFAULT_CHANGES_LEN_MIN)
+        });
+        let changes_len_max = self.config.changes_len_max.unwrap_or_else(|| {
+            languages
+                .iter()
+                .map(|language| language.changes_len_max)
+                .max()
+                .unwrap_or(DEFAULT_CHANGES_LEN_MAX)
+        });
+
+        (changes_len_min, changes_len_max)
+    }
+
+    /// Check if the author name or email of a given commit matches the bot pattern.
+    fn is_bot_commit(&self, commit: &Commit) -> bool {
+        let bot_pattern = match &self.bot_pattern {
+            Some(bot_pattern) => bot_pattern,
+            None => return false,
+        };
+        let author = commit.author();
+        let is_match = |text: Option<&str>| text.is_some_and(|text| bot_pattern.is_match(text));
+        is_match(author.name()) || is_match(author.email())
+    }
+
+    /// Process a commit.
+    fn process_commit(&self, commit: &Commit) -> Result<Vec<Record>> {
+        let commit_oid = commit.id();
+
+        // Check if commit excluded
+        if self.excluded_commit_ids.contains(&commit_oid) {
+            info!("Skip commit #{commit_oid} (excluded)");
+            self.count_skip("excluded");
+            return Ok(Vec::new());
+        }
+
+        // Check if commit saved in a previous run
+        if self.resumed_commit_ids.contains(&commit_oid) {
+            info!("Skip commit #{commit_oid} (already saved in a previous run)");
+            self.count_skip("resumed");
+            return Ok(Vec::new());
+        }
+
+        // Check if commit reverted later
+        if self.reverted_commit_ids.contains(&commit_oid) {
+            info!("Skip commit #{commit_oid} (reverted by a later commit)");
+            self.count_skip("reverted");
+            return Ok(Vec::new());
+        }
+
+        // Check if commit already processed
+        if self.processed_commit_ids.contains(&commit_oid) {
+            info!("Skip commit #{commit_oid} (already processed)");
+            self.count_skip("already_processed");
+            return Ok(Vec::new());
+        }
+
+        // Check if commit has parents
+        if commit.parent_count() == 0 {
+            info!("Skip commit #{commit_oid} (no parents)");
+            self.count_skip("no_parents");
+            return Ok(Vec::new());
+        }
+
+        // Check if the commit author is selected
+        if let Some(selected_authors) = &self.selected_authors {
+            if !selected_authors.contains(&Self::get_author_key(commit)) {
+                info!("Skip commit #{commit_oid} (author not selected)");
+                self.count_skip("author_not_selected");
+                return Ok(Vec::new());
+            }
+        }
+
+        // Check if the commit is authored within the date range
+        let author_time = commit.author().when().seconds();
+        if self.config.since.is_some_and(|since| author_time < since)
+            || self.config.until.is_some_and(|until| author_time > until)
+        {
+            info!("Skip commit #{commit_oid} (authored outside the date range)");
+            self.count_skip("date_range");
+            return Ok(Vec::new());
+        }
+
+        // Check if the commit is kept by the sampling
+        if !self.is_sampled_commit(commit_oid) {
+            info!("Skip commit #{commit_oid} (not sampled)");
+            self.count_skip("not_sampled");
+            return Ok(Vec::new());
+        }
+
+        // Check if the limit of commits saved on the commit day is reached
+        if let Some(max_per_day) = self.config.max_per_day {
+            let nb_commits_saved_on_day = self
+                .nb_commits_saved_per_day
+                .get(&Self::get_commit_day(commit))
+                .copied()
+                .unwrap_or(0);
+            if nb_commits_saved_on_day >= max_per_day {
+                info!("Skip commit #{commit_oid} (limit of commits per day reached)");
+                self.count_skip("max_per_day");
+                return Ok(Vec::new());
+            }
+        }
+
+        // Get commit parent
+        let parent = match commit.parent(0) {
+            Ok(parent) => parent,
+            Err(_) => {
+                info!("Skip commit #{commit_oid} (failed to fetch parent)");
+                self.count_skip("parent_unavailable");
+                return Ok(Vec::new());
+            }
+        };
+
+        // Get commit and parent trees, the parent tree being replaced by the baseline if any
+        let parent_tree = match self.baseline_tree_id {
+            Some(baseline_tree_id) => self.git_repo.find_tree(baseline_tree_id),
+            None => parent.tree(),
+        };
+        let (commit_tree, parent_tree) = match (commit.tree(), parent_tree) {
+            (Ok(commit_tree), Ok(parent_tree)) => (commit_tree, parent_tree),
+            _ => {
+                info!("Skip commit #{commit_oid} (failed to fetch trees)");
+                self.count_skip("trees_unavailable");
+                return Ok(Vec::new());
+            }
+        };
+
+        // Check if bot commit
+        if self.is_bot_commit(commit) {
+            info!("Skip commit #{commit_oid} (commit author indicates a bot)");
+            self.count_skip("bot");
+            return Ok(Vec::new());
+        }
+
+        // Get commit message
+        let commit_message = match self.get_commit_message(commit) {
+            Some(message) => message,
+            None => {
+                info!("Skip commit #{commit_oid} (commit message out of required length)");
+                self.count_skip("message_length");
+                return Ok(Vec::new());
+            }
+        };
+
+        // Check number of distinct characters in commit message
+        if let Some(min_unique_chars) = self.config.min_unique_chars {
+            if commit_message.chars().collect::<HashSet<char>>().len() < min_unique_chars {
+                info!("Skip commit #{commit_oid} (commit message has too few distinct characters)");
+                self.count_skip("message_unique_chars");
+                return Ok(Vec::new());
+            }
+        }
+
+        // Check if commit message matches an exclude pattern, before the include patterns
+        if let Some(pattern) = self
+            .message_exclude_patterns
+            .iter()
+            .find(|pattern| pattern.is_match(&commit_message))
+        {
+            info!("Skip commit #{commit_oid} (commit message matches the exclude pattern '{pattern}')");
+            self.count_skip("message_excluded");
+            return Ok(Vec::new());
+        }
+        if !self.message_include_patterns.is_empty()
+            && !self
+                .message_include_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(&commit_message))
+        {
+            info!("Skip commit #{commit_oid} (commit message matches no include pattern)");
+            self.count_skip("message_not_included");
+            return Ok(Vec::new());
+        }
+
+        // Check if commit message is templated
+        if self.config.skip_templated && self.is_templated_message(commit) {
+            info!("Skip commit #{commit_oid} (commit message contains template artifacts)");
+            self.count_skip("templated_message");
+            return Ok(Vec::new());
+        }
+
+        // Check if commit message is a bare file name
+        if self.config.skip_filename_messages && self.is_filename_message(&commit_message) {
+            info!("Skip commit #{commit_oid} (commit message is a file name)");
+            self.count_skip("filename_message");
+            return Ok(Vec::new());
+        }
+
+        // Check if normalized commit message duplicates the one of a saved commit
+        let message_key = self
+            .config
+            .dedup_messages_normalized
+            .then(|| self.normalize_message(&commit_message));
+        if let Some(message_key) = &message_key {
+            if self.saved_message_keys.contains(message_key) {
+                info!("Skip commit #{commit_oid} (commit message duplicates a saved one)");
+                self.count_skip("duplicate_message");
+                return Ok(Vec::new());
+            }
+        }
+
+        // Get commit changes, unless computed ahead
+        let prefetched_changes = self.prefetched_changes.borrow_mut().remove(&commit_oid);
+        let changes = match prefetched_changes {
+            Some(changes) => Ok(changes),
+            None => self.get_commit_changes(&commit_tree, &parent_tree),
+        };
+        let CommitChanges { files, extensions } = match changes {
+            Ok(Changes::Target(changes)) => changes,
+            Ok(Changes::Binary(file_path)) if self.config.fail_on_binary => {
+                return Err(anyhow!(
+                    "commit #{commit_oid} changes the binary file '{}'",
+                    file_path.display()
+                ));
+            }
+            Ok(Changes::Binary(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (binary file '{}' changed)",
+                    file_path.display()
+                );
+                self.count_skip("binary");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::FileTooLarge(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (too many changed lines in '{}')",
+                    file_path.display()
+                );
+                self.count_skip("file_too_large");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::FileDiffTooLong(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (diff of '{}' too long)",
+                    file_path.display()
+                );
+                self.count_skip("file_diff_too_long");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::ConflictMarkers(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (merge conflict markers added in '{}')",
+                    file_path.display()
+                );
+                self.count_skip("conflict_markers");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::NotUtf8(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (invalid UTF-8 in the changes of '{}')",
+                    file_path.display()
+                );
+                self.count_skip("not_utf8");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::Deletion(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (file '{}' deleted)",
+                    file_path.display()
+                );
+                self.count_skip("deletion");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::NotAddition(file_path)) => {
+                info!(
+                    "Skip commit #{commit_oid} (existing file '{}' changed)",
+                    file_path.display()
+                );
+                self.count_skip("not_addition");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::OnlyMigrations) => {
+                info!("Skip commit #{commit_oid} (only database migration files changed)");
+                self.count_skip("only_migrations");
+                return Ok(Vec::new());
+            }
+            Ok(Changes::NoTarget) => {
+                info!("Skip commit #{commit_oid} (no changes in files with target extensions)");
+                self.count_skip("no_target");
+                return Ok(Vec::new());
+            }
+            Err(_) => {
+                info!("Skip commit #{commit_oid} (failed to read commit changes)");
+                self.count_skip("changes_unreadable");
+                return Ok(Vec::new());
+            }
+        };
+
+        // Check number of distinct target extensions changed
+        if let Some(min_languages) = self.config.min_languages {
+            if extensions.len() < min_languages {
+                info!("Skip commit #{commit_oid} (too few distinct target extensions changed)");
+                self.count_skip("min_languages");
+                return Ok(Vec::new());
+            }
+        }
+
+        // Check number of files with target extensions changed
+        let nb_files = files.len();
+        if self
+            .config
+            .min_files
+            .is_some_and(|min_files| nb_files < min_files)
+            || self
+                .config
+                .max_files
+                .is_some_and(|max_files| nb_files > max_files)
+        {
+            info!("Skip commit #{commit_oid} ({nb_files} files with target extensions changed, out of required bounds)");
+            self.count_skip("files_count");
+            return Ok(Vec::new());
+        }
+
+        // Get number of commits of the author
+        let author_commits = if self.config.include_author_experience {
+            self.author_commit_counts
+                .get(&Self::get_author_key(commit))
+                .copied()
+        } else {
+            None
+        };
+
+        // Get body of the commit message
+        let commit_body = self
+            .config
+            .include_body
+            .then(|| Self::get_commit_body(commit));
+
+        // Mask identifiers of the commit message
+        let (commit_message, commit_body) = if self.config.mask_identifiers {
+            (
+                self.mask_identifiers(&commit_message),
+                commit_body.map(|body| self.mask_identifiers(&body)),
+            )
+        } else {
+            (commit_message, commit_body)
+        };
+
+        // Get committer
+        let (committer_name, committer_email) = if self.config.include_committer {
+            let committer = commit.committer();
+            (
+                Some(committer.name().unwrap_or_default().to_string()),
+                Some(committer.email().unwrap_or_default().to_string()),
+            )
+        } else {
+            (None, None)
+        };
+
+        // Get author and commit time
+        let (author_name, author_email, commit_time) = if self.config.include_metadata {
+            let author = commit.author();
+            (
+                Some(author.name().unwrap_or_default().to_string()),
+                Some(author.email().unwrap_or_default().to_string()),
+                Some(format_time(commit.time())),
+            )
+        } else {
+            (None, None, None)
+        };
+
+        // Get the output file partition, by hash of the author
+        let partition = match (self.config.partition_by.as_deref(), self.config.partitions) {
+            (Some("author"), Some(partitions)) => {
+                (fnv1a(Self::get_author_key(commit).as_bytes()) % partitions as u64) as usize
+            }
+            _ => 0,
+        };
+
+        // Detect commit message language
+        let message_lang = self
+            .config
+            .include_message_lang
+            .then(|| Self::detect_message_lang(&commit_message));
+
+        // Encode commit message
+        let commit_message = encode_text(commit_message, self.config.encode_message.as_deref());
+        let commit_body =
+            commit_body.map(|body| encode_text(body, self.config.encode_message.as_deref()));
+
+        // Create a record for each file changed, with the data of the commit
+        let commit_record = Record {
+            commit_id: commit_oid,
+            extension: OsString::new(),
+            partition,
+            message_key,
+            commit_hash: (!self.config.no_hash).then(|| commit_oid.to_string()),
+            index: None,
+            commit_message,
+            commit_body,
+            commit_changes: String::new(),
+            file_path: None,
+            changes_preview: None,
+            author_commits,
+            author_name,
+            author_email,
+            commit_time,
+            committer_name,
+            committer_email,
+            message_lang,
+            repo: self.config.repo_label.clone(),
+            depth: self.commit_depths.get(&commit_oid).copied(),
+            function_context: None,
+            lines_added: None,
+            lines_removed: None,
+        };
+
+        Ok(self.create_records(
+            &format!("commit #{commit_oid}"),
+            commit_record,
+            CommitChanges { files, extensions },
+        ))
+    }
+
+    /// Process uncommitted changes of the working directory as a single commit without message,
+    /// or as one per file changed.
+    fn process_workdir(&self) -> Result<Vec<Record>> {
+        // Get working directory changes
+        let changes = match self.get_workdir_changes()? {
+            Changes::Target(changes) => changes,
+            Changes::Binary(file_path) if self.config.fail_on_binary => {
+                return Err(anyhow!(
+                    "the working directory changes the binary file '{}'",
+                    file_path.display()
+                ));
+            }
+            _ => {
+                info!("Skip working directory changes (no qualifying changes in files with target extensions)");
+                return Ok(Vec::new());
+            }
+        };
+
+        // Create a record for each file changed, without commit data
+        let commit_record = Record {
+            commit_id: Oid::zero(),
+            extension: OsString::new(),
+            partition: 0,
+            message_key: None,
+            commit_hash: (!self.config.no_hash).then(String::new),
+            index: None,
+            commit_message: String::new(),
+            commit_body: self.config.include_body.then(String::new),
+            commit_changes: String::new(),
+            file_path: None,
+            changes_preview: None,
+            author_commits: None,
+            author_name: None,
+            author_email: None,
+            commit_time: None,
+            committer_name: None,
+            committer_email: None,
+            message_lang: None,
+            repo: self.config.repo_label.clone(),
+            depth: None,
+            function_context: None,
+            lines_added: None,
+            lines_removed: None,
+        };
+
+        Ok(self.create_records("working directory changes", commit_record, changes))
+    }
+
+    /// Create the records of the changes of a commit, described by `subject` in the logs, for each
+    /// file changed or a single one for all of them.
+    /// The data of the commit is that of a given record, whose changes are left empty.
+    fn create_records(
+        &self,
+        subject: &str,
+        commit_record: Record,
+        changes: CommitChanges,
+    ) -> Vec<Record> {
+        let CommitChanges { files, extensions } = changes;
+        let files = if self.config.per_file {
+            files
+        } else {
+            FileChanges::join(files).into_iter().collect()
+        };
+        let mut records = Vec::new();
+        for file in files {
+            let FileChanges {
+                path,
+                extension,
+                content: mut commit_changes,
+                function_contexts,
+                line_stats,
+            } = file;
+
+            // Trim trailing whitespace from commit changes
+            if self.config.trim_changes_trailing {
+                let trimmed_len = commit_changes.trim_end().len();
+                commit_changes.truncate(trimmed_len);
+            }
+
+            // Check commit changes length, against the bounds of the file alone if saved per file
+            let (changes_len_min, changes_len_max) = if self.config.per_file {
+                self.get_changes_len_bounds(&HashSet::from([extension.clone()]))
+            } else {
+                self.get_changes_len_bounds(&extensions)
+            };
+            let changes_len = measure_len(&commit_changes, &self.config.changes_len_unit);
+            if changes_len < changes_len_min
+                || (changes_len > changes_len_max && !self.config.truncate_changes)
+            {
+                info!("Skip {subject} (commit changes out of required length)");
+                self.count_skip("changes_length");
+                continue;
+            }
+            if changes_len > changes_len_max {
+                commit_changes = truncate_len(
+                    &commit_changes,
+                    changes_len_max,
+                    &self.config.changes_len_unit,
+                );
+            }
+
+            // Check if the allocation of the extension is reached
+            if let Some(extension_allocations) = &self.extension_allocations {
+                let allocation = extension_allocations.get(&extension).copied().unwrap_or(0);
+                let nb_commits_saved = self
+                    .nb_commits_saved_per_extension
+                    .get(&extension)
+                    .copied()
+                    .unwrap_or(0);
+                if nb_commits_saved >= allocation {
+                    info!("Skip {subject} (allocation of the extension reached)");
+                    self.count_skip("extension_allocation");
+                    continue;
+                }
+            }
+
+            // Mask identifiers of commit changes
+            if self.config.mask_identifiers && self.config.mask_identifiers_in_changes {
+                commit_changes = self.mask_identifiers(&commit_changes);
+            }
+
+            // Get preview of commit changes
+            let changes_preview = self
+                .config
+                .preview_len
+                .map(|preview_len| commit_changes.chars().take(preview_len).collect());
+
+            // Get function contexts of commit changes
+            let function_context = self
+                .config
+                .include_function_context
+                .then(|| function_contexts.join("\n"));
+
+            // Encode commit changes
+            let commit_changes = encode_text(commit_changes, self.config.encode_changes.as_deref());
+            let changes_preview = changes_preview
+                .map(|preview| encode_text(preview, self.config.encode_changes.as_deref()));
+
+            // Check if the record duplicates a saved one
+            if self.config.dedup
+                && self
+                    .saved_record_hashes
+                    .contains(&hash_record(&commit_record.commit_message, &commit_changes))
+            {
+                info!("Skip {subject} (record duplicates a saved one)");
+                self.count_skip("duplicate_record");
+                continue;
+            }
+
+            // Get index of the record in the dataset
+            let index = self
+                .config
+                .include_index
+                .then_some(self.nb_commits_saved + records.len());
+
+            // Create a new record for this file, or for all of them
+            records.push(Record {
+                extension,
+                index,
+                commit_changes,
+                file_path: self
+                    .config
+                    .per_file
+                    .then(|| path.to_string_lossy().into_owned()),
+                changes_preview,
+                function_context,
+                lines_added: self.config.include_stats.then_some(line_stats.added),
+                lines_removed: self.config.include_stats.then_some(line_stats.removed),
+                ..commit_record.clone()
+            });
+        }
+
+        records
+    }
+
+    /// Check if a given Git commit is kept by the sampling.
+    /// The sampling only depends on the commit id and the seed, so the walk order does not matter.
+    fn is_sampled_commit(&self, commit_oid: Oid) -> bool {
+        let sample = match self.config.sample {
+            Some(sample) => sample,
+            None => return true,
+        };
+
+        // Hash the seed and the commit id, tagged to stay independent of the split
+        let hash = fnv1a(
+            b"sample"
+                .iter()
+                .chain(&self.config.seed.to_le_bytes())
+                .chain(commit_oid.as_bytes()),
+        );
+
+        (hash as f64 / u64::MAX as f64) < sample
+    }
+
+    /// Check if a given record belongs to the test split.
+    /// The split only depends on the commit id and the split seed, or the seed if none is given.
+    fn is_test_record(&self, record: &Record) -> bool {
+        let split = match self.config.split {
+            Some(split) => split,
+            None => return false,
+        };
+
+        // Hash the split seed and the commit id, tagged to stay independent of the sampling
+        let split_seed = self.config.split_seed.unwrap_or(self.config.seed);
+        let hash = fnv1a(
+            b"split"
+                .iter()
+                .chain(&split_seed.to_le_bytes())
+                .chain(record.commit_id.as_bytes()),
+        );
+
+        (hash as f64 / u64::MAX as f64) < split
+    }
+
+    /// Get the columns of the dataset, in the same order as the record fields.
+    fn get_columns(&self) -> Vec<Column> {
+        let column = |default_name: &'static str, kind, flag| Column {
+            name: self
+                .column_names
+                .get(default_name)
+                .cloned()
+                .unwrap_or_else(|| default_name.to_string()),
+            default_name,
+            kind,
+            flag,
+            encoding: match default_name {
+                "commit_message" | "commit_body" => self.config.encode_message.clone(),
+                "commit_changes" | "changes_preview" => self.config.encode_changes.clone(),
+                _ => None,
+            },
+        };
+
+        let mut columns = Vec::new();
+        if !self.config.no_hash {
+            columns.push(column("commit_hash", "string", None));
+        }
+        if self.config.include_index {
+            columns.push(column("index", "integer", Some("--include-index")));
+        }
+        columns.push(column("commit_message", "string", None));
+        if self.config.include_body {
+            columns.push(column("commit_body", "string", Some("--include-body")));
+        }
+        columns.push(column("commit_changes", "string", None));
+        if self.config.per_file {
+            columns.push(column("file_path", "string", Some("--per-file")));
+        }
+        if self.config.preview_len.is_some() {
+            columns.push(column("changes_preview", "string", Some("--preview-len")));
+        }
+        if self.config.include_author_experience {
+            columns.push(column(
+                "author_commits",
+                "integer",
+                Some("--include-author-experience"),
+            ));
+        }
+        if self.config.include_metadata {
+            columns.push(column("author_name", "string", Some("--include-metadata")));
+            columns.push(column("author_email", "string", Some("--include-metadata")));
+            columns.push(column("commit_time", "string", Some("--include-metadata")));
+        }
+        if self.config.include_committer {
+            columns.push(column(
+                "committer_name",
+                "string",
+                Some("--include-committer"),
+            ));
+            columns.push(column(
+                "committer_email",
+                "string",
+                Some("--include-committer"),
+            ));
+        }
+        if self.config.include_message_lang {
+            columns.push(column(
+                "message_lang",
+                "string",
+                Some("--include-message-lang"),
+            ));
+        }
+        if self.config.repo_label.is_some() {
+            columns.push(column("repo", "string", Some("--repos-file")));
+        }
+        if self.config.include_depth {
+            columns.push(column("depth", "integer", Some("--include-depth")));
+        }
+        if self.config.include_function_context {
+            columns.push(column(
+                "function_context",
+                "string",
+                Some("--include-function-context"),
+            ));
+        }
+        if self.config.include_stats {
+            columns.push(column("lines_added", "integer", Some("--include-stats")));
+            columns.push(column("lines_removed", "integer", Some("--include-stats")));
+        }
+        columns
+    }
+
+    /// Check that the renamed columns are part of the output, and that the column names are
+    /// non-empty and unique.
+    fn check_columns(&self) -> Result<()> {
+        let columns = self.get_columns();
+
+        for default_name in self.column_names.keys() {
+            if !columns
+                .iter()
+                .any(|column| column.default_name == default_name)
+            {
+                return Err(anyhow!(
+                    "cannot rename the column '{default_name}', which is not part of the output"
+                ));
+            }
+        }
+
+        let mut names = HashSet::new();
+        for column in &columns {
+            if column.name.is_empty() {
+                return Err(anyhow!(
+                    "the column '{}' cannot be renamed to an empty name",
+                    column.default_name
+                ));
+            }
+            if !names.insert(&column.name) {
+                return Err(anyhow!("duplicate column name '{}'", column.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the schema of the dataset to a file as JSON.
+    fn save_schema(&self, path: &Path) -> Result<()> {
+        let schema = Schema {
+            schema_version: SCHEMA_VERSION,
+            columns: self.get_columns(),
+        };
+
+        let schema_file =
+            File::create(path).map_err(|e| anyhow!("failed to create the schema file ({e})"))?;
+        serde_json::to_writer_pretty(schema_file, &schema)
+            .map_err(|e| anyhow!("failed to write the schema file ({e})"))?;
+
+        Ok(())
+    }
+
+    /// Create a CSV writer, whose fields and records are terminated by NUL without quoting with the
+    /// `null-delimited` format.
+    fn create_csv_writer<W: Write>(&self, writer: W) -> csv::Writer<W> {
+        let mut builder = csv::WriterBuilder::new();
+        builder.delimiter(self.config.delimiter).quote_style(
+            match self.config.quote_style.as_str() {
+                "always" => csv::QuoteStyle::Always,
+                "never" => csv::QuoteStyle::Never,
+                _ => csv::QuoteStyle::Necessary,
+            },
+        );
+        if self.config.format == "null-delimited" {
+            builder
+                .delimiter(b'\0')
+                .terminator(csv::Terminator::Any(b'\0'))
+                .quote_style(csv::QuoteStyle::Never);
+        }
+        builder.from_writer(writer)
+    }
+
+    /// Get a given record as a JSON object keyed by column name.
+    fn get_json_object(&self, record: &Record) -> Result<Map<String, Value>> {
+        let object: Map<String, Value> = serde_json::to_value(record)
+            .and_then(serde_json::from_value)
+            .map_err(|e| anyhow!("failed to serialize json record ({e})"))?;
+
+        // Rename columns
+        Ok(object
+            .into_iter()
+            .map(|(name, value)| match self.column_names.get(&name) {
+                Some(new_name) => (new_name.clone(), value),
+                None => (name, value),
+            })
+            .collect())
+    }
+
+    /// Get the header of the dataset written in the CSV format.
+    fn get_header_bytes(&self) -> Result<Vec<u8>> {
+        let mut wtr = self.create_csv_writer(Vec::new());
+        let header = self.get_columns().into_iter().map(|column| column.name);
+        wtr.write_record(header)
+            .map_err(|e| anyhow!("failed to write csv header ({e})"))?;
+        wtr.into_inner()
+            .map_err(|e| anyhow!("failed to write csv header ({e})"))
+    }
+
+    /// Get a given record written in the output format.
+    fn get_record_bytes(&self, record: &Record) -> Result<Vec<u8>> {
+        if self.config.format == "jsonl" {
+            let mut object = serde_json::to_vec(&self.get_json_object(record)?)
+                .map_err(|e| anyhow!("failed to write json record ({e})"))?;
+            object.push(b'\n');
+            return Ok(object);
+        }
+
+        let mut wtr = self.create_csv_writer(Vec::new());
+        wtr.write_record(record.fields())
+            .map_err(|e| anyhow!("failed to write csv record ({e})"))?;
+        wtr.into_inner()
+            .map_err(|e| anyhow!("failed to write csv record ({e})"))
+    }
+
+    /// Open the rotated output file with the given index to write records to.
+    fn open_output_writer(&self, path: &Path, index: usize) -> Result<OutputWriter> {
+        let (writer, file, size): (Box<dyn Write>, Option<File>, u64) =
+            if path == Path::new(STDOUT_OUTPUT) {
+                (Box::new(io::stdout()), None, 0)
+            } else {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(Self::get_rotated_path(path, index))
+                    .map_err(|e| anyhow!("failed to open the output file ({e})"))?;
+                let size = file.metadata()?.len();
+                (Box::new(file.try_clone()?), Some(file), size)
+            };
+
+        // A JSON array cannot be appended to
+        if self.config.json_pretty && size != 0 {
+            return Err(anyhow!(
+                "cannot write a JSON array to the non-empty output file"
+            ));
+        }
+
+        Ok(OutputWriter {
+            path: path.to_path_buf(),
+            index,
+            buffer: BufWriter::with_capacity(self.config.write_buffer_size as usize, writer),
+            file,
+            size,
+            // Only empty files get a header, in the CSV formats, and the standard output once
+            header_pending: size == 0
+                && !self.config.json_pretty
+                && self.config.format != "jsonl"
+                && !(path == Path::new(STDOUT_OUTPUT) && self.config.stdout_header_written),
+            json_objects: Vec::new(),
+        })
+    }
+
+    /// Open an output file to write records to, continuing with its last rotated output file.
+    fn open_last_output_writer(&self, path: &Path) -> Result<OutputWriter> {
+        let mut index = 0;
+        if self.config.rotate_size.is_some() {
+            while Self::get_rotated_path(path, index + 1).exists() {
+                index += 1;
+            }
+        }
+        self.open_output_writer(path, index)
+    }
+
+    /// Get the paths of the output files, one per partition.
+    fn get_output_paths(&self, output: &Path) -> Vec<PathBuf> {
+        match self.config.partitions {
+            Some(partitions) => (0..partitions)
+                .map(|partition| Self::get_suffixed_path(output, &format!("part{partition}")))
+                .collect(),
+            None => vec![output.to_path_buf()],
+        }
+    }
+
+    /// Read the message and changes of the records of an existing output file.
+    fn read_output_records(&self, path: &Path) -> Result<Vec<(String, String)>> {
+        let names = ["commit_message", "commit_changes"].map(|name| {
+            self.column_names
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string())
+        });
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read the output file ({e})"))?;
+        if content.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Get records as rows of fields under a header
+        let (header, rows): (Vec<String>, Vec<Vec<String>>) = match self.config.format.as_str() {
+            "jsonl" => {
+                let mut records = Vec::new();
+                for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                    let object: Map<String, Value> = serde_json::from_str(line)
+                        .map_err(|e| anyhow!("failed to parse the output file ({e})"))?;
+                    let field = |name: &str| {
+                        object
+                            .get(name)
+                            .and_then(Value::as_str)
+                            .map(str::to_string)
+                            .ok_or_else(|| {
+                                anyhow!("failed to parse the output file (no {name} field)")
+                            })
+                    };
+                    records.push((field(&names[0])?, field(&names[1])?));
+                }
+                return Ok(records);
+            }
+            "null-delimited" => {
+                // Fields are grouped by the number of columns of the header
+                let mut fields: Vec<String> = content.split('\0').map(str::to_string).collect();
+                fields.pop();
+                let nb_columns = self.get_columns().len();
+                let rows: Vec<Vec<String>> =
+                    fields.chunks(nb_columns).map(<[String]>::to_vec).collect();
+                match rows.split_first() {
+                    Some((header, rows)) => (header.clone(), rows.to_vec()),
+                    None => return Ok(Vec::new()),
+                }
+            }
+            _ => {
+                let mut rdr = csv::ReaderBuilder::new()
+                    .delimiter(self.config.delimiter)
+                    .from_reader(content.as_bytes());
+                let header = rdr
+                    .headers()
+                    .map_err(|e| anyhow!("failed to parse the output file ({e})"))?
+                    .iter()
+                    .map(str::to_string)
+                    .collect();
+                let rows = rdr
+                    .records()
+                    .map(|row| row.map(|row| row.iter().map(str::to_string).collect()))
+                    .collect::<Result<Vec<Vec<String>>, csv::Error>>()
+                    .map_err(|e| anyhow!("failed to parse the output file ({e})"))?;
+                (header, rows)
+            }
+        };
+
+        let positions = names
+            .iter()
+            .map(|name| {
+                header
+                    .iter()
+                    .position(|column| column == name)
+                    .ok_or_else(|| anyhow!("the output file has no {name} column"))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        Ok(rows
+            .into_iter()
+            .filter(|row| row.len() == header.len())
+            .map(|row| (row[positions[0]].clone(), row[positions[1]].clone()))
+            .collect())
+    }
+
+    /// Load the hashes of the records of the existing output files, rotated ones included, for
+    /// records duplicating them to be skipped.
+    fn load_existing_records(&mut self) -> Result<()> {
+        let output = match &self.config.output {
+            Some(output) if !is_stdout_output(Some(output)) => output,
+            _ => return Ok(()),
+        };
+        let mut paths = self.get_output_paths(output);
+        paths.extend(self.config.test_output.clone());
+
+        let mut record_hashes = HashSet::new();
+        for path in paths {
+            for index in 0.. {
+                let rotated_path = Self::get_rotated_path(&path, index);
+                if !rotated_path.exists() {
+                    break;
+                }
+                for (commit_message, commit_changes) in self.read_output_records(&rotated_path)? {
+                    record_hashes.insert(hash_record(&commit_message, &commit_changes));
+                }
+            }
+        }
+        self.saved_record_hashes.extend(record_hashes);
+
+        Ok(())
+    }
+
+    /// Open the output files and the resume state file, for the saved records to be written to
+    /// them as soon as they are saved.
+    fn open_dataset(&self) -> Result<()> {
+        let output = self
+            .config
+            .output
+            .as_deref()
+            .unwrap_or_else(|| Path::new(STDOUT_OUTPUT));
+        let outputs = self
+            .get_output_paths(output)
+            .iter()
+            .map(|path| self.open_last_output_writer(path))
+            .collect::<Result<Vec<OutputWriter>>>()?;
+        let test_output = match &self.config.test_output {
+            Some(path) => Some(self.open_last_output_writer(path)?),
+            None => None,
+        };
+        let resume_state = match &self.config.resume_state {
+            Some(path) => {
+                let state_file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| anyhow!("failed to open the resume state file ({e})"))?;
+                Some(BufWriter::new(state_file))
+            }
+            None => None,
+        };
+
+        *self.dataset_writer.borrow_mut() = Some(DatasetWriter {
+            outputs,
+            test_output,
+            resume_state,
+        });
+
+        Ok(())
+    }
+
+    /// Flush the buffered records to an output file.
+    fn flush_output(&self, output: &mut OutputWriter) -> Result<()> {
+        output
+            .buffer
+            .flush()
+            .map_err(|e| anyhow!("failed to write the output file ({e})"))?;
+
+        // Make sure the written records reach the disk
+        if let Some(file) = output.file.as_ref().filter(|_| self.config.fsync) {
+            file.sync_all()
+                .map_err(|e| anyhow!("failed to sync the output file ({e})"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the given bytes to an output file, preceded by the header if it is still empty,
+    /// rotating to a new numbered output file beforehand once the current one reaches the
+    /// rotation size.
+    fn write_output(&self, output: &mut OutputWriter, bytes: &[u8]) -> Result<()> {
+        // Rotate if the current output file is full
+        if let Some(rotate_size) = self.config.rotate_size {
+            while output.size >= rotate_size {
+                self.flush_output(output)?;
+                *output = self.open_output_writer(&output.path, output.index + 1)?;
+            }
+        }
+
+        // Lock the file against concurrent writers
+        if let Some(file) = output.file.as_ref().filter(|_| self.config.append_lock) {
+            file.lock_exclusive()
+                .map_err(|e| anyhow!("failed to lock the output file ({e})"))?;
+
+            // Another writer may have written the header meanwhile
+            output.header_pending &= file.metadata()?.len() == 0;
+        }
+
+        // Write header
+        if output.header_pending {
+            let header = self.get_header_bytes()?;
+            output
+                .buffer
+                .write_all(&header)
+                .map_err(|e| anyhow!("failed to write the output file ({e})"))?;
+            output.size += header.len() as u64;
+            output.header_pending = false;
+        }
+
+        output
+            .buffer
+            .write_all(bytes)
+            .map_err(|e| anyhow!("failed to write the output file ({e})"))?;
+        output.size += bytes.len() as u64;
+
+        // Unlock the file once the bytes are written
+        if let Some(file) = output.file.as_ref().filter(|_| self.config.append_lock) {
+            output
+                .buffer
+                .flush()
+                .map_err(|e| anyhow!("failed to write the output file ({e})"))?;
+            FileExt::unlock(file).map_err(|e| anyhow!("failed to unlock the output file ({e})"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a given saved record to its output file, and its commit id to the resume state file.
+    fn write_record(&self, record: &Record) -> Result<()> {
+        let mut dataset_writer = self.dataset_writer.borrow_mut();
+        let dataset_writer = dataset_writer
+            .as_mut()
+            .ok_or_else(|| anyhow!("no output file open to save the record to"))?;
+
+        // Route record to the test output file or the output file of its partition
+        let output = match &mut dataset_writer.test_output {
+            Some(test_output) if self.is_test_record(record) => test_output,
+            _ => &mut dataset_writer.outputs[record.partition],
+        };
+        if self.config.json_pretty {
+            // A JSON array is only written once complete
+            output.json_objects.push(self.get_json_object(record)?);
+        } else {
+            self.write_output(output, &self.get_record_bytes(record)?)?;
+        }
+
+        if let Some(resume_state) = &mut dataset_writer.resume_state {
+            writeln!(resume_state, "{}", record.commit_id)
+                .map_err(|e| anyhow!("failed to write the resume state file ({e})"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Finish writing the output files and the resume state file, and close them.
+    fn close_dataset(&self) -> Result<()> {
+        let dataset_writer = match self.dataset_writer.borrow_mut().take() {
+            Some(dataset_writer) => dataset_writer,
+            None => return Ok(()),
+        };
+
+        for mut output in dataset_writer
+            .outputs
+            .into_iter()
+            .chain(dataset_writer.test_output)
+        {
+            if self.config.json_pretty {
+                let objects = serde_json::to_vec_pretty(&output.json_objects)
+                    .map_err(|e| anyhow!("failed to write json records ({e})"))?;
+                self.write_output(&mut output, &objects)?;
+            } else if output.header_pending
+                && (self.config.header_on_empty || output.file.is_none())
+            {
+                // Write the header alone to the output files left empty, and always to the
+                // standard output
+                self.write_output(&mut output, &[])?;
+            }
+            self.flush_output(&mut output)?;
+        }
+
+        if let Some(mut resume_state) = dataset_writer.resume_state {
+            resume_state
+                .flush()
+                .map_err(|e| anyhow!("failed to write the resume state file ({e})"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Save the given records to the output files, and their commit ids to the resume state file.
+    pub fn save_dataset(&self, records: &[Record]) -> Result<()> {
+        self.open_dataset()?;
+        for record in records {
+            self.write_record(record)?;
+        }
+        self.close_dataset()
+    }
+
+    /// Write a checkpoint of the run after the given last visited commit, with the sizes of the
+    /// output files and resume state file, once their records are flushed.
+    fn write_checkpoint(&self, commit_oid: Oid) -> Result<()> {
+        let path = match &self.config.checkpoint {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let mut dataset_writer = self.dataset_writer.borrow_mut();
+        let dataset_writer = match dataset_writer.as_mut() {
+            Some(dataset_writer) => dataset_writer,
+            None => return Ok(()),
+        };
+
+        let mut checkpoint = format!("commit {commit_oid}\nsaved {}\n", self.nb_commits_saved);
+        for output in dataset_writer
+            .outputs
+            .iter_mut()
+            .chain(dataset_writer.test_output.as_mut())
+        {
+            self.flush_output(output)?;
+            checkpoint.push_str(&format!(
+                "output {} {} {}\n",
+                output.index,
+                output.size,
+                output.path.display()
+            ));
+        }
+        if let Some(resume_state) = &mut dataset_writer.resume_state {
+            resume_state
+                .flush()
+                .map_err(|e| anyhow!("failed to write the resume state file ({e})"))?;
+            let size = resume_state.get_ref().metadata()?.len();
+            checkpoint.push_str(&format!("state {size}\n"));
+        }
+
+        // Replace the previous checkpoint at once, for an interruption not to corrupt it
+        let tmp_path = Self::get_suffixed_path(path, "tmp");
+        fs::write(&tmp_path, checkpoint)
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .map_err(|e| anyhow!("failed to write the checkpoint file ({e})"))?;
+        info!("Write checkpoint after commit #{commit_oid}");
+
+        Ok(())
+    }
+
+    /// Read the checkpoint of an interrupted run, if any, rolling the output files and resume
+    /// state file back to their sizes at the checkpoint, so that records written after it are
+    /// not saved twice.
+    /// Return the last commit visited and the number of commits saved before the checkpoint.
+    fn restore_checkpoint(config: &Config) -> Result<Option<Checkpoint>> {
+        let path = match config.checkpoint.as_ref().filter(|path| path.exists()) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let checkpoint = fs::read_to_string(path)
+            .map_err(|e| anyhow!("failed to read the checkpoint file ({e})"))?;
+        let invalid = |line: &str| anyhow!("invalid line in the checkpoint file '{line}'");
+
+        let mut commit_oid = None;
+        let mut nb_commits_saved = 0;
+        let mut rollbacks = Vec::new();
+        for line in checkpoint.lines() {
+            match line.split_once(' ') {
+                Some(("commit", oid)) => {
+                    commit_oid = Some(Oid::from_str(oid).map_err(|_| invalid(line))?);
+                }
+                Some(("saved", count)) => {
+                    nb_commits_saved = count.parse().map_err(|_| invalid(line))?;
+                }
+                Some(("output", output)) => {
+                    let mut fields = output.splitn(3, ' ');
+                    let (index, size, output_path) =
+                        match (fields.next(), fields.next(), fields.next()) {
+                            (Some(index), Some(size), Some(output_path)) => {
+                                (index, size, output_path)
+                            }
+                            _ => return Err(invalid(line)),
+                        };
+                    let index: usize = index.parse().map_err(|_| invalid(line))?;
+                    let size: u64 = size.parse().map_err(|_| invalid(line))?;
+                    let output_path = PathBuf::from(output_path);
+
+                    // Output files rotated after the checkpoint only hold records written after it
+                    let mut next_index = index + 1;
+                    while Self::get_rotated_path(&output_path, next_index).exists() {
+                        next_index += 1;
+                    }
+                    for rotated_index in index + 1..next_index {
+                        rollbacks.push((Self::get_rotated_path(&output_path, rotated_index), None));
+                    }
+                    rollbacks.push((Self::get_rotated_path(&output_path, index), Some(size)));
+                }
+                Some(("state", size)) => {
+                    let state_path = config
+                        .resume_state
+                        .clone()
+                        .ok_or_else(|| anyhow!("the checkpoint file refers to a resume state file (use --resume-state)"))?;
+                    rollbacks.push((state_path, Some(size.parse().map_err(|_| invalid(line))?)));
+                }
+                _ => return Err(invalid(line)),
+            }
+        }
+        let commit_oid = commit_oid.ok_or_else(|| anyhow!("no commit in the checkpoint file"))?;
+
+        // Roll the files back, removing output files rotated after the checkpoint
+        for (rollback_path, size) in rollbacks {
+            let result = match size {
+                Some(size) => OpenOptions::new()
+                    .write(true)
+                    .open(&rollback_path)
+                    .and_then(|file| file.set_len(size)),
+                None => fs::remove_file(&rollback_path),
+            };
+            result.map_err(|e| {
+                anyhow!(
+                    "failed to roll '{}' back to the checkpoint ({e})",
+                    rollback_path.display()
+                )
+            })?;
+        }
+
+        Ok(Some(Checkpoint {
+            commit_oid,
+            nb_commits_saved,
+        }))
+    }
+
+    /// Get the path of the rotated output file with the given index.
+    /// For example, the rotated output file 1 of `output.csv` is `output.1.csv`.
+    fn get_rotated_path(path: &Path, index: usize) -> PathBuf {
+        if index == 0 {
+            return path.to_path_buf();
+        }
+        Self::get_suffixed_path(path, &index.to_string())
+    }
+
+    /// Get a path with a suffix inserted before its extension.
+    /// For example, `output.csv` with the suffix `part1` is `output.part1.csv`.
+    fn get_suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+        let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+        file_name.push(".");
+        file_name.push(suffix);
+        if let Some(ext) = path.extension() {
+            file_name.push(".");
+            file_name.push(ext);
+        }
+        path.with_file_name(file_name)
+    }
+
+    /// Get the statistics of the run.
+    fn get_stats(&self) -> Stats {
+        Stats {
+            commits_scanned: self.nb_commits_scanned,
+            commits_processed: self.processed_commit_ids.len(),
+            commits_saved: self.nb_commits_saved,
+            commits_skipped: self.nb_commits_skipped.borrow().clone(),
+            scan_limit_reached: self
+                .config
+                .max_scan
+                .is_some_and(|max_scan| self.nb_commits_scanned >= max_scan),
+            ..Stats::default()
+        }
+    }
+
+    /// Run the extractor and save the dataset, saving the statistics of the run even if it
+    /// terminates early.
+    pub fn run(&mut self) -> Result<()> {
+        let start = Instant::now();
+        let result = self.extract_and_save();
+        let mut stats = self.get_stats();
+        if result.is_ok() {
+            stats.print_summary(self.config.output.as_deref())?;
+        }
+
+        // Save statistics of the run
+        if let Some(path) = &self.config.stats_out {
+            stats.elapsed_secs = start.elapsed().as_secs_f64();
+            stats.error = result.as_ref().err().map(|e| e.to_string());
+            return result.and(stats.save(path));
+        }
+
+        result
+    }
+
+    /// Compute ahead in parallel the changes of the commits to process for the given commits,
+    /// each thread opening its own Git repository.
+    /// Commits rejected before their changes are needed are left out.
+    fn prefetch_changes(&self, diff_pool: &ThreadPool, commit_oids: &[Oid]) -> Result<()> {
+        let mut candidate_oids = Vec::new();
+        for commit_oid in commit_oids {
+            let commit = self.git_repo.find_commit(*commit_oid)?;
+            for commit in self.get_commits_to_process(commit) {
+                let commit_oid = commit.id();
+                if commit.parent_count() > 0
+                    && !self.excluded_commit_ids.contains(&commit_oid)
+                    && !self.resumed_commit_ids.contains(&commit_oid)
+                    && !self.reverted_commit_ids.contains(&commit_oid)
+                    && !self.processed_commit_ids.contains(&commit_oid)
+                    && self.get_commit_message(&commit).is_some()
+                {
+                    candidate_oids.push(commit_oid);
+                }
+            }
+        }
+
+        let git_repo_path = self.git_repo.path();
+        let baseline_tree_id = self.baseline_tree_id;
+        let diff_analyzer = self.get_diff_analyzer();
+        let changes: Vec<(Oid, Changes)> = diff_pool.install(|| {
+            candidate_oids
+                .par_iter()
+                .map_init(
+                    || Repository::open(git_repo_path).ok(),
+                    |git_repo, commit_oid| {
+                        // Changes failing to be computed are computed again when processed
+                        let git_repo = git_repo.as_ref()?;
+                        let commit = git_repo.find_commit(*commit_oid).ok()?;
+                        let parent_tree = match baseline_tree_id {
+                            Some(baseline_tree_id) => git_repo.find_tree(baseline_tree_id),
+                            None => commit.parent(0).and_then(|parent| parent.tree()),
+                        };
+                        let changes = diff_analyzer
+                            .get_tree_changes(git_repo, &commit.tree().ok()?, &parent_tree.ok()?)
+                            .ok()?;
+                        Some((*commit_oid, changes))
+                    },
+                )
+                .flatten()
+                .collect()
+        });
+
+        // Replace the changes left over from the previous batch
+        let mut prefetched_changes = self.prefetched_changes.borrow_mut();
+        prefetched_changes.clear();
+        prefetched_changes.extend(changes);
+
+        Ok(())
+    }
+
+    /// Walk the Git commits reachable from the ref, saving records until the dataset size is reached.
+    fn walk_commits(&mut self) -> Result<()> {
+        // Create revwalk to iterate on commits
+        let mut revwalk = Self::create_revwalk(&self.git_repo, &self.config)?;
+        let mut nb_consecutive_skips = 0;
+        let mut boundary_reached = false;
+        let mut checkpoint_oid = self.checkpoint_oid.take();
+        let mut last_visited_oid = None;
+        let mut nb_commits_checkpointed = self.nb_commits_saved;
+
+        // Take commits in batches, whose diffs are computed ahead in parallel
+        let batch_size = match &self.diff_pool {
+            Some(diff_pool) => diff_pool.current_num_threads() * PREFETCH_BATCH_SIZE_PER_THREAD,
+            None => 1,
+        };
+
+        while !boundary_reached && self.nb_commits_saved < self.config.size {
+            let mut commit_oids = Vec::with_capacity(batch_size);
+            while commit_oids.len() < batch_size
+                && self
+                    .config
+                    .max_scan
+                    .is_none_or(|max_scan| self.nb_commits_scanned + commit_oids.len() < max_scan)
+            {
+                let commit_oid = match revwalk.next() {
+                    Some(commit_oid) => commit_oid,
+                    None => break,
+                };
+                if commit_oid.is_ok() {
+                    self.scan_progress.inc(1);
+                }
+                match commit_oid {
+                    // Commits up to the checkpoint were visited by the interrupted run
+                    Ok(commit_oid) if checkpoint_oid.is_some() => {
+                        if checkpoint_oid == Some(commit_oid) {
+                            info!("Resume after the checkpoint commit #{commit_oid}");
+                            checkpoint_oid = None;
+                        }
+                    }
+                    Ok(commit_oid) => commit_oids.push(commit_oid),
+                    // Commits beyond the boundary of a shallow clone are missing
+                    Err(e) if self.git_repo.is_shallow() => {
+                        info!("Stop at the boundary of the shallow clone ({e})");
+                        boundary_reached = true;
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            if commit_oids.is_empty() {
+                break;
+            }
+            if let Some(diff_pool) = &self.diff_pool {
+                self.prefetch_changes(diff_pool, &commit_oids)?;
+            }
+
+            for commit_oid in commit_oids {
+                // Check if dataset size has been reached
+                if self.nb_commits_saved >= self.config.size {
+                    break;
+                }
+                self.nb_commits_scanned += 1;
+
+                let commit = self.git_repo.find_commit(commit_oid)?;
+
+                for commit in self.get_commits_to_process(commit) {
+                    let records = self.process_commit(&commit)?;
+                    if records.is_empty() {
+                        nb_consecutive_skips += 1;
+                    } else {
+                        info!("Save commit #{:?}", commit.id());
+                        *self
+                            .nb_commits_saved_per_day
+                            .entry(Self::get_commit_day(&commit))
+                            .or_insert(0) += 1;
+                        nb_consecutive_skips = 0;
+                    }
+                    for record in records {
+                        // Files of the commit beyond the dataset size are not saved
+                        if self.nb_commits_saved >= self.config.size {
+                            break;
+                        }
+                        *self
+                            .nb_commits_saved_per_extension
+                            .entry(record.extension.clone())
+                            .or_insert(0) += 1;
+                        if let Some(message_key) = &record.message_key {
+                            self.saved_message_keys.insert(message_key.clone());
+                        }
+                        if self.config.dedup {
+                            self.saved_record_hashes.insert(hash_record(
+                                &record.commit_message,
+                                &record.commit_changes,
+                            ));
+                        }
+                        // Write record as soon as saved if the output files are open
+                        if self.dataset_writer.borrow().is_some() {
+                            self.write_record(&record)?;
+                        } else {
+                            self.records.push(record);
+                        }
+                        self.nb_commits_saved += 1;
+                        self.progress_bar.inc(1);
+                    }
+                    self.processed_commit_ids.insert(commit.id());
+
+                    // Check if too many commits were skipped in a row
+                    if let Some(max_consecutive_skips) = self.config.max_consecutive_skips {
+                        if nb_consecutive_skips >= max_consecutive_skips {
+                            return Err(anyhow!(
+                                "{nb_consecutive_skips} commits in a row were skipped without any saved, the filters may be too strict (use RUST_LOG=info to see the reasons)"
+                            ));
+                        }
+                    }
+                }
+                last_visited_oid = Some(commit_oid);
+
+                // Write a checkpoint once enough records were saved since the last one
+                if self.nb_commits_saved - nb_commits_checkpointed
+                    >= self.config.checkpoint_interval
+                {
+                    self.write_checkpoint(commit_oid)?;
+                    nb_commits_checkpointed = self.nb_commits_saved;
+                }
+            }
+        }
+
+        // The checkpoint of an interrupted run must be found again, unless its dataset is complete
+        if let Some(checkpoint_oid) =
+            checkpoint_oid.filter(|_| self.nb_commits_saved < self.config.size)
+        {
+            return Err(anyhow!(
+                "the checkpoint commit {checkpoint_oid} was not visited by the history walk, the history may have been rewritten (delete the checkpoint file to start over)"
+            ));
+        }
+        if let Some(last_visited_oid) = last_visited_oid {
+            self.write_checkpoint(last_visited_oid)?;
+        }
+
+        Ok(())
+    }
+
+    /// Extract records from the commits of the Git repository, without saving them.
+    pub fn extract(&mut self) -> Result<Vec<Record>> {
+        // Check names of the output columns
+        self.check_columns()?;
+
+        // Count commits per author
+        if self.config.include_author_experience || self.config.max_authors.is_some() {
+            let authors = self.count_author_commits()?;
+
+            // Select the authors whose commits can be saved
+            if let Some(max_authors) = self.config.max_authors {
+                self.selected_authors = Some(self.select_authors(authors, max_authors));
+            }
+        }
+
+        // Verify commits saved in previous runs
+        if self.config.resume_verify {
+            self.verify_resumed_commits()?;
+        }
+
+        // Compute depth of commits
+        if self.config.include_depth {
+            self.compute_commit_depths()?;
+        }
+
+        // Load records of the existing output files
+        if self.config.dedup {
+            self.load_existing_records()?;
+        }
+
+        // Collect commits reverted later
+        if self.config.skip_reverted {
+            self.collect_reverted_commits()?;
+        }
+
+        // Allocate the dataset size across extensions
+        if self.config.proportional {
+            self.extension_allocations = Some(self.allocate_extensions()?);
+        }
+
+        if self.config.workdir {
+            // Extract uncommitted changes of the working directory, without walking the history
+            for record in self.process_workdir()? {
+                info!("Save working directory changes");
+                if self.dataset_writer.borrow().is_some() {
+                    self.write_record(&record)?;
+                } else {
+                    self.records.push(record);
+                }
+                self.nb_commits_saved += 1;
+                self.progress_bar.inc(1);
+            }
+        } else {
+            self.walk_commits()?;
+        }
+
+        Ok(mem::take(&mut self.records))
+    }
+
+    /// Extract records from the commits of the Git repository and save them.
+    fn extract_and_save(&mut self) -> Result<()> {
+        // Open output files, for records to be written as soon as saved
+        self.open_dataset()?;
+        let result = self.extract();
+
+        // Close output files, keeping the records written before any failure
+        let close_result = self.close_dataset();
+        result.and(close_result)?;
+
+        // Save schema of the dataset
+        if let Some(path) = &self.config.schema_out {
+            self.save_schema(path)?;
+        }
+
+        // Finish and clear progress bars
+        self.scan_progress.finish_and_clear();
+        self.progress_bar.finish_and_clear();
+
+        Ok(())
+    }
+}
+
+/// Check if the output path stands for the standard output, as does a missing one.
+fn is_stdout_output(output: Option<&Path>) -> bool {
+    output.is_none_or(|output| output == Path::new(STDOUT_OUTPUT))
+}
+
+/// Get the writer of the run summary, the standard error when the records go to the standard output.
+fn get_summary_writer(output: Option<&Path>) -> Box<dyn Write> {
+    if is_stdout_output(output) {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+/// Complete command line arguments with the options of their `--config` file, if any, that they
+/// do not give.
+/// Keys are the long names of the options, such as `message-len-max` or `message_len_max`.
+/// A boolean is the value of an option taking one, such as `skip-binary`, otherwise `true` sets a
+/// flag and `false` leaves it unset. An array repeats its option with each of its values, and any
+/// other value is the value of its option.
+fn with_config_file_args(mut args: Vec<OsString>) -> Result<Vec<OsString>> {
+    let path = match args.iter().position(|arg| arg == "--config") {
+        Some(position) => args.get(position + 1).map(PathBuf::from),
+        None => args.iter().find_map(|arg| {
+            arg.to_str()
+                .and_then(|arg| arg.strip_prefix("--config="))
+                .map(PathBuf::from)
+        }),
+    };
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(args),
+    };
+    let content = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("failed to read the config file '{}' ({e})", path.display()))?;
+    let table: toml::Table = content
+        .parse()
+        .map_err(|e| anyhow!("failed to parse the config file '{}' ({e})", path.display()))?;
+
+    let mut file_args = Vec::new();
+    for (key, value) in table {
+        let name = format!("--{}", key.replace('_', "-"));
+        // Options of the command line take precedence
+        let is_given = args.iter().any(|arg| {
+            arg.to_str().is_some_and(|arg| {
+                arg == name
+                    || arg
+                        .strip_prefix(&name)
+                        .is_some_and(|arg| arg.starts_with('='))
+            })
+        });
+        if is_given {
+            continue;
+        }
+        let values = match value {
+            toml::Value::Boolean(true) if !takes_value(&name) => {
+                file_args.push(OsString::from(&name));
+                continue;
+            }
+            toml::Value::Boolean(false) if !takes_value(&name) => continue,
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                toml::Value::Boolean(value) => value.to_string(),
+                _ => {
+                    return Err(anyhow!(
+                        "unsupported value of '{key}' in the config file (expected a string, a number, a boolean or an array of them)"
+                    ))
+                }
+            };
+            file_args.push(OsString::from(format!("{name}={value}")));
+        }
+    }
+    args.extend(file_args);
+
+    Ok(args)
+}
+
+/// Check if a given command line option, such as `--skip-binary`, takes a value rather than being
+/// a flag, as only then does the command line keep the value given to it.
+fn takes_value(name: &str) -> bool {
+    Config::clap()
+        .get_matches_from_safe([
+            "gitex",
+            "--repository=.",
+            "--extensions=rs",
+            "--size=1",
+            &format!("{name}=true"),
+        ])
+        .is_ok_and(|matches| matches.value_of(name.trim_start_matches('-')).is_some())
+}
+
+/// Read the Git repositories listed in a file, one `path[,label]` per line, with their labels.
+/// A repository without label is labeled by its path.
+fn read_repos_file(path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let content =
+        fs::read_to_string(path).map_err(|e| anyhow!("failed to read the repos file ({e})"))?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once(',') {
+            Some((path, label)) => (PathBuf::from(path.trim()), label.trim().to_string()),
+            None => (PathBuf::from(line), line.to_string()),
+        })
+        .collect())
+}
+
+/// Parse a size in bytes with an optional unit (B, KB, MB, GB), e.g. `100MB`.
+fn parse_size(size: &str) -> Result<u64> {
+    let size = size.trim();
+    let unit_start = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (value, unit) = size.split_at(unit_start);
+    let value: u64 = value
+        .parse()
+        .map_err(|e| anyhow!("invalid size '{size}' ({e})"))?;
+    let multiplier = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => {
+            return Err(anyhow!(
+                "invalid size unit '{unit}' (expected B, KB, MB or GB)"
+            ))
+        }
+    };
+    Ok(value * multiplier)
+}
+
+/// Parse a CSV delimiter, a single byte such as `;`, or `\t` for a tab.
+fn parse_delimiter(delimiter: &str) -> Result<u8> {
+    match delimiter {
+        "\\t" => Ok(b'\t'),
+        _ => match delimiter.as_bytes() {
+            [byte] => Ok(*byte),
+            _ => Err(anyhow!(
+                "invalid delimiter '{delimiter}' (expected a single byte)"
+            )),
+        },
+    }
+}
+
+/// Parse a date as a number of seconds since the epoch, such as `2023-05-01`,
+/// `2023-05-01T12:00:00` or `2023-05-01T12:00:00+02:00`, in UTC unless an offset is given.
+/// A date without time stands for the start of the day, or for its end with `end_of_day`.
+fn parse_date(date: &str, end_of_day: bool) -> Result<i64> {
+    let invalid = || {
+        anyhow!("invalid date '{date}' (expected YYYY-MM-DD, optionally followed by THH:MM:SS and an offset)")
+    };
+    let parse_numbers = |text: &str, separator| {
+        text.split(separator)
+            .map(|number| number.parse::<i64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<i64>>>()
+    };
+
+    let date = date.trim();
+    let (day, time) = match date.split_once('T') {
+        Some((day, time)) => (day, Some(time)),
+        None => (date, None),
+    };
+
+    // Convert the civil date to a number of days since the epoch
+    let (year, month, day) = match parse_numbers(day, '-')?[..] {
+        [year, month, day] if (1..=12).contains(&month) => (year, month, day),
+        _ => return Err(invalid()),
+    };
+    let is_leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if is_leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(invalid());
+    }
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let time = match time {
+        Some(time) => time,
+        None if end_of_day => return Ok(days * 86400 + 86399),
+        None => return Ok(days * 86400),
+    };
+
+    // Split the offset from the time
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(offset_start) => time.split_at(offset_start),
+        None => (time, ""),
+    };
+    let seconds = match parse_numbers(time, ':')?[..] {
+        [hours, minutes, seconds] if hours < 24 && minutes < 60 && seconds < 60 => {
+            hours * 3600 + minutes * 60 + seconds
+        }
+        _ => return Err(invalid()),
+    };
+    let offset = match offset {
+        "" | "Z" => 0,
+        _ => match parse_numbers(&offset[1..], ':')?[..] {
+            [hours, minutes] if hours < 24 && minutes < 60 => {
+                let offset_seconds = hours * 3600 + minutes * 60;
+                if offset.starts_with('-') {
+                    -offset_seconds
+                } else {
+                    offset_seconds
+                }
+            }
+            _ => return Err(invalid()),
+        },
+    };
+
+    Ok(days * 86400 + seconds - offset)
+}
+
+/// Parse the start date of the commits to save, inclusive.
+fn parse_since(date: &str) -> Result<i64> {
+    parse_date(date, false)
+}
+
+/// Parse the end date of the commits to save, inclusive.
+fn parse_until(date: &str) -> Result<i64> {
+    parse_date(date, true)
+}
+
+/// Error of a run saving no commit with `--fail-on-empty`.
+#[derive(Debug)]
+pub struct EmptyDatasetError;
+
+impl fmt::Display for EmptyDatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no commits matched the filters, the dataset is empty")
+    }
+}
+
+impl Error for EmptyDatasetError {}
+
+/// Extract data from the commits of the configured Git repository, or of each Git repository
+/// listed in the repos file, and save it.
+pub fn run(config: Config) -> Result<()> {
+    let fail_on_empty = config.fail_on_empty;
+    let nb_commits_saved;
+
+    // Extract data from commits of each listed Git repository, with statistics summed over them
+    if let Some(repos_file) = &config.repos_file {
+        let start = Instant::now();
+        let mut stats = Stats::default();
+        let mut result = Ok(());
+        let mut nb_repos_extracted = 0;
+        for (repository, label) in read_repos_file(repos_file)? {
+            if !repository.exists() {
+                eprintln!(
+                    "Warning: skip missing Git repository '{}'",
+                    repository.display()
+                );
+                continue;
+            }
+            if Repository::open(&repository).is_err() {
+                eprintln!(
+                    "Warning: skip '{}', which is not a Git repository",
+                    repository.display()
+                );
+                continue;
+            }
+            let mut extractor = Extractor::new(Config {
+                repository: Some(repository),
+                repo_label: Some(label),
+                // The records of the next repositories follow the header on the standard output
+                stdout_header_written: nb_repos_extracted > 0,
+                // The schema is the same for every repository
+                schema_out: config
+                    .schema_out
+                    .clone()
+                    .filter(|_| nb_repos_extracted == 0),
+                ..config.clone()
+            })?;
+            result = extractor.extract_and_save();
+            stats.add(extractor.get_stats());
+            if result.is_err() {
+                break;
+            }
+            nb_repos_extracted += 1;
+        }
+        if result.is_ok() {
+            stats.print_summary(config.output.as_deref())?;
+        }
+
+        // Save statistics of the run
+        if let Some(path) = &config.stats_out {
+            stats.elapsed_secs = start.elapsed().as_secs_f64();
+            stats.error = result.as_ref().err().map(|e| e.to_string());
+            result = result.and(stats.save(path));
+        }
+        result?;
+        nb_commits_saved = stats.commits_saved;
+    } else {
+        // Extract data from commits
+        let mut extractor = Extractor::new(config)?;
+        extractor.run()?;
+        nb_commits_saved = extractor.nb_commits_saved;
+    }
+
+    // Check if any commit is saved
+    if fail_on_empty && nb_commits_saved == 0 {
+        return Err(EmptyDatasetError.into());
+    }
+
+    Ok(())
+}
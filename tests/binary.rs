@@ -0,0 +1,56 @@
+mod common;
+
+use common::{extract, extract_messages, Fixture};
+use gitex::{ConfigBuilder, Extractor};
+
+/// Create a fixture repository whose last commit changes a binary file with a target extension.
+fn create_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.commit("Add the main module", &[("main.py", b"print()\n")]);
+    fixture.commit(
+        "Add the compiled module",
+        &[("compiled.py", b"\x00\x01\x02\x03\xff\n")],
+    );
+    fixture
+}
+
+/// Get a configuration extracting the commits of a given fixture.
+fn config(fixture: &Fixture) -> ConfigBuilder {
+    ConfigBuilder::new(fixture.path())
+        .extensions(&["py"])
+        .size(2)
+}
+
+#[test]
+fn binary_file_is_skipped_by_default() {
+    let fixture = create_fixture();
+
+    assert_eq!(extract_messages(config(&fixture)), ["Add the main module"]);
+}
+
+#[test]
+fn binary_file_is_kept_without_skip_binary() {
+    let fixture = create_fixture();
+
+    let records = extract(config(&fixture).option("skip-binary", "false"));
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].commit_message, "Add the compiled module");
+    assert!(records[0]
+        .commit_changes
+        .contains("Binary files /dev/null and b/compiled.py differ"));
+}
+
+#[test]
+fn binary_file_fails_with_fail_on_binary() {
+    let fixture = create_fixture();
+
+    let config = config(&fixture).flag("fail-on-binary").build().unwrap();
+    let error = Extractor::new(config)
+        .and_then(|mut extractor| extractor.extract())
+        .err()
+        .expect("binary file extracted");
+    assert!(error
+        .to_string()
+        .contains("changes the binary file 'compiled.py'"));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{extract_messages, Fixture};
+use gitex::ConfigBuilder;
+
+/// Create a fixture repository with commits of bots and of humans whose name contains `bot`.
+fn create_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for (author, email) in [
+        (
+            "dependabot[bot]",
+            "49699333+dependabot[bot]@users.noreply.github.com",
+        ),
+        (
+            "github-actions[bot]",
+            "41898282+github-actions[bot]@users.noreply.github.com",
+        ),
+        ("Renovate", "bot@renovateapp.com"),
+        ("Jane Abbott", "jane.abbott@example.com"),
+        ("Pieter Botha", "botha@example.com"),
+    ] {
+        let content = format!("// {author}\n");
+        fixture.commit_as(
+            author,
+            email,
+            &format!("Update the module of {author}"),
+            &[("main.rs", content.as_bytes())],
+        );
+    }
+    fixture
+}
+
+#[test]
+fn default_bot_pattern_skips_authors_containing_bot() {
+    let fixture = create_fixture();
+    fixture.commit_as(
+        "Dependabot",
+        "support@dependabot.com",
+        "Bump the serde version",
+        &[("main.rs", b"// serde\n")],
+    );
+
+    let messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(6),
+    );
+
+    assert!(messages.is_empty(), "{messages:?}");
+}
+
+#[test]
+fn word_bot_pattern_keeps_humans_containing_bot() {
+    let fixture = create_fixture();
+
+    let messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(5)
+            .option("bot-pattern", r"(?i)\bbot\b"),
+    );
+
+    assert_eq!(
+        messages,
+        [
+            "Update the module of Pieter Botha",
+            "Update the module of Jane Abbott",
+        ]
+    );
+}
+
+#[test]
+fn no_bot_filter_keeps_bots() {
+    let fixture = Fixture::new();
+    fixture.commit_as(
+        "dependabot[bot]",
+        "49699333+dependabot[bot]@users.noreply.github.com",
+        "Bump the serde version",
+        &[("main.rs", b"fn main() {}\n")],
+    );
+
+    let messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .flag("no-bot-filter"),
+    );
+
+    assert_eq!(messages, ["Bump the serde version"]);
+}
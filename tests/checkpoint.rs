@@ -0,0 +1,113 @@
+mod common;
+
+use std::fs;
+
+use common::{run_gitex, Fixture};
+
+/// Create a fixture repository of 5 commits changing Rust files.
+fn create_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for index in 0..5 {
+        let content = format!("fn function_{index}() {{}}\n");
+        fixture.commit(
+            &format!("Add the function number {index}"),
+            &[(&format!("f{index}.rs"), content.as_bytes())],
+        );
+    }
+    fixture
+}
+
+#[test]
+fn resumed_run_completes_the_dataset_size() {
+    let fixture = create_fixture();
+    let repository = fixture.path().to_str().unwrap();
+    let output = fixture.path().join("output.jsonl");
+    let checkpoint = fixture.path().join("checkpoint");
+    let run = |size: &str| {
+        let result = run_gitex(&[
+            "--repository",
+            repository,
+            "--extensions",
+            "rs",
+            "--format",
+            "jsonl",
+            "--output",
+            output.to_str().unwrap(),
+            "--checkpoint",
+            checkpoint.to_str().unwrap(),
+            "--checkpoint-interval",
+            "1",
+            "--size",
+            size,
+        ]);
+        assert!(
+            result.status.success(),
+            "{}",
+            String::from_utf8_lossy(&result.stderr)
+        );
+        fs::read_to_string(&output).unwrap()
+    };
+
+    // An interrupted run saves fewer records than its size
+    assert_eq!(run("1").lines().count(), 1);
+
+    // Resuming saves the remaining records only, once
+    let records = run("2");
+    assert_eq!(records.lines().count(), 2);
+    assert!(records.contains("Add the function number 4"));
+    assert!(records.contains("Add the function number 3"));
+
+    // Resuming a complete run saves no more records
+    assert_eq!(run("2").lines().count(), 2);
+}
+
+#[test]
+fn checkpoint_conflicts_with_repos_file() {
+    let fixture = create_fixture();
+    let repos_file = fixture.path().join("repos");
+    fs::write(&repos_file, fixture.path().to_str().unwrap()).unwrap();
+
+    let result = run_gitex(&[
+        "--repos-file",
+        repos_file.to_str().unwrap(),
+        "--extensions",
+        "rs",
+        "--size",
+        "2",
+        "--checkpoint",
+        fixture.path().join("checkpoint").to_str().unwrap(),
+    ]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn unreachable_checkpoint_fails_before_walking() {
+    let fixture = create_fixture();
+    let repository = fixture.path().to_str().unwrap();
+    let output = fixture.path().join("output.jsonl");
+    let checkpoint = fixture.path().join("checkpoint");
+    let run = |git_ref: &str| {
+        run_gitex(&[
+            "--repository",
+            repository,
+            "--extensions",
+            "rs",
+            "--output",
+            output.to_str().unwrap(),
+            "--checkpoint",
+            checkpoint.to_str().unwrap(),
+            "--size",
+            "1",
+            "--ref",
+            git_ref,
+        ])
+    };
+    assert!(run("HEAD").status.success());
+
+    // The checkpoint commit is newer than the ref
+    let result = run("HEAD~2");
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("is not reachable from the ref"));
+    assert!(!String::from_utf8_lossy(&result.stdout).contains("Total commits scanned"));
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::fs;
+
+use common::{extract, run_gitex, Fixture};
+use gitex::{ConfigBuilder, Extractor};
+
+#[test]
+fn workdir_runs_with_default_options() {
+    let fixture = Fixture::new();
+    fixture.commit("Add the main module", &[("main.rs", b"fn main() {}\n")]);
+    fs::write(
+        fixture.path().join("main.rs"),
+        "fn main() {}\nfn run() {}\n",
+    )
+    .unwrap();
+
+    let repository = fixture.path().to_str().unwrap();
+    let output = run_gitex(&[
+        "--repository",
+        repository,
+        "--extensions",
+        "rs",
+        "--size",
+        "1",
+        "--workdir",
+    ]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("commit_hash,commit_message,commit_changes\n"));
+    assert!(stdout.contains("+fn run() {}"));
+}
+
+#[test]
+fn workdir_rejects_ref_and_sort() {
+    let fixture = Fixture::new();
+    let repository = fixture.path().to_str().unwrap();
+    for option in [["--ref", "HEAD~1"], ["--sort", "topological"]] {
+        let output = run_gitex(&[
+            "--repository",
+            repository,
+            "--extensions",
+            "rs",
+            "--size",
+            "1",
+            "--workdir",
+            option[0],
+            option[1],
+        ]);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(&format!("{} cannot be used with --workdir", option[0])));
+    }
+}
+
+#[test]
+fn workdir_saves_every_file_regardless_of_size() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Add the modules",
+        &[("main.rs", b"fn main() {}\n"), ("lib.rs", b"fn lib() {}\n")],
+    );
+    fs::write(
+        fixture.path().join("main.rs"),
+        "fn main() {}\nfn run() {}\n",
+    )
+    .unwrap();
+    fs::write(fixture.path().join("lib.rs"), "fn lib() {}\nfn test() {}\n").unwrap();
+
+    let records = extract(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .flag("workdir")
+            .flag("per-file"),
+    );
+
+    let file_paths: Vec<_> = records
+        .into_iter()
+        .map(|record| record.file_path.unwrap())
+        .collect();
+    assert_eq!(file_paths, ["lib.rs", "main.rs"]);
+}
+
+#[test]
+fn applicable_patches_reject_options_dropping_lines() {
+    let fixture = Fixture::new();
+    for option in [
+        "added-only",
+        "removed-only",
+        "content-only",
+        "trim-changes-trailing",
+        "drop-whitespace-only-lines",
+        "truncate-changes",
+        "mask-identifiers-in-changes",
+    ] {
+        let config = ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("patch-format", "applicable")
+            .flag("mask-identifiers")
+            .flag(option)
+            .build()
+            .unwrap();
+        let Err(error) = Extractor::new(config) else {
+            panic!("--{option} accepted with applicable patches");
+        };
+        assert_eq!(
+            error.to_string(),
+            format!("--{option} cannot be used with --patch-format applicable")
+        );
+    }
+}
@@ -0,0 +1,161 @@
+#![allow(dead_code)]
+
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+use git2::{Oid, Repository, ResetType, Signature, Time};
+use gitex::{ConfigBuilder, Extractor, Record};
+use tempfile::TempDir;
+
+/// Time of the first commit of a fixture repository, each next commit being a minute later.
+const FIXTURE_START_TIME: i64 = 1_700_000_000;
+
+/// Git repository created in a temporary directory, whose commits are made one file change at a
+/// time in a deterministic order.
+pub struct Fixture {
+    /// Temporary directory of the working directory.
+    dir: TempDir,
+    /// The Git repository.
+    pub repo: Repository,
+    /// Number of commits made.
+    nb_commits: Cell<i64>,
+}
+
+impl Fixture {
+    /// Create an empty Git repository with a root commit, which is never saved as it has no parent.
+    pub fn new() -> Fixture {
+        let dir = TempDir::new().expect("failed to create the fixture directory");
+        let repo = Repository::init(dir.path()).expect("failed to create the fixture repository");
+        let fixture = Fixture {
+            dir,
+            repo,
+            nb_commits: Cell::new(0),
+        };
+        fixture.commit("Initial commit", &[]);
+        fixture
+    }
+
+    /// Get the path of the working directory.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Commit the given files, written to the working directory, with the given author.
+    pub fn commit_as(
+        &self,
+        author: &str,
+        email: &str,
+        message: &str,
+        files: &[(&str, &[u8])],
+    ) -> Oid {
+        let mut index = self.repo.index().expect("failed to open the index");
+        for (path, content) in files {
+            let file_path = self.path().join(path);
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent).expect("failed to create the fixture directory");
+            }
+            fs::write(&file_path, content).expect("failed to write the fixture file");
+            index
+                .add_path(Path::new(path))
+                .expect("failed to add the fixture file");
+        }
+        index.write().expect("failed to write the index");
+        self.commit_index(author, email, message)
+    }
+
+    /// Commit the given files, written to the working directory.
+    pub fn commit(&self, message: &str, files: &[(&str, &[u8])]) -> Oid {
+        self.commit_as("Alice", "alice@example.com", message, files)
+    }
+
+    /// Commit the rename of a file, written anew with the given content.
+    pub fn rename(&self, message: &str, from: &str, to: &str, content: &[u8]) -> Oid {
+        let mut index = self.repo.index().expect("failed to open the index");
+        fs::remove_file(self.path().join(from)).expect("failed to remove the fixture file");
+        index
+            .remove_path(Path::new(from))
+            .expect("failed to remove the fixture file");
+        fs::write(self.path().join(to), content).expect("failed to write the fixture file");
+        index
+            .add_path(Path::new(to))
+            .expect("failed to add the fixture file");
+        index.write().expect("failed to write the index");
+        self.commit_index("Alice", "alice@example.com", message)
+    }
+
+    /// Create a branch at a given commit and check it out, the next commits being made on it.
+    pub fn checkout_new_branch(&self, name: &str, commit_oid: Oid) {
+        let commit = self
+            .repo
+            .find_commit(commit_oid)
+            .expect("failed to find the commit");
+        self.repo
+            .branch(name, &commit, false)
+            .expect("failed to create the branch");
+        self.repo
+            .set_head(&format!("refs/heads/{name}"))
+            .expect("failed to check out the branch");
+        self.repo
+            .reset(commit.as_object(), ResetType::Hard, None)
+            .expect("failed to reset to the branch");
+    }
+
+    /// Commit the index on top of HEAD, if any.
+    fn commit_index(&self, author: &str, email: &str, message: &str) -> Oid {
+        let nb_commits = self.nb_commits.get();
+        self.nb_commits.set(nb_commits + 1);
+        let time = Time::new(FIXTURE_START_TIME + nb_commits * 60, 0);
+        let signature =
+            Signature::new(author, email, &time).expect("failed to create the signature");
+        let tree_id = self
+            .repo
+            .index()
+            .and_then(|mut index| index.write_tree())
+            .expect("failed to write the tree");
+        let tree = self
+            .repo
+            .find_tree(tree_id)
+            .expect("failed to find the tree");
+        let parent = self
+            .repo
+            .head()
+            .ok()
+            .and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        self.repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .expect("failed to commit")
+    }
+}
+
+/// Extract the records of a given configuration.
+pub fn extract(config: ConfigBuilder) -> Vec<Record> {
+    let config = config.build().expect("invalid configuration");
+    let mut extractor = Extractor::new(config).expect("failed to create the extractor");
+    extractor.extract().expect("failed to extract")
+}
+
+/// Extract the commit messages of the records of a given configuration.
+pub fn extract_messages(config: ConfigBuilder) -> Vec<String> {
+    extract(config)
+        .into_iter()
+        .map(|record| record.commit_message)
+        .collect()
+}
+
+/// Run the gitex command with the given arguments.
+pub fn run_gitex(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_gitex"))
+        .args(args)
+        .output()
+        .expect("failed to run gitex")
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use std::fs;
+
+use common::{extract_messages, Fixture};
+use gitex::ConfigBuilder;
+use tempfile::TempDir;
+
+/// Extract the commit messages of a fixture with a given config file.
+fn extract_messages_with_config_file(fixture: &Fixture, content: &str) -> Vec<String> {
+    let dir = TempDir::new().unwrap();
+    let config_file = dir.path().join("gitex.toml");
+    fs::write(&config_file, content).unwrap();
+    extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(2)
+            .option("config", &config_file),
+    )
+}
+
+#[test]
+fn config_file_sets_boolean_options_and_flags() {
+    let fixture = Fixture::new();
+    fixture.commit("Add the main module", &[("main.rs", b"fn main() {}\n")]);
+    fixture.commit("Add the binary data", &[("data.rs", b"\0\x01\x02\n")]);
+
+    assert_eq!(
+        extract_messages_with_config_file(&fixture, "skip_binary = true\n"),
+        ["Add the main module"]
+    );
+    assert_eq!(
+        extract_messages_with_config_file(&fixture, "skip_binary = false\nlossy = false\n"),
+        ["Add the binary data", "Add the main module"]
+    );
+}
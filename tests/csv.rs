@@ -0,0 +1,50 @@
+mod common;
+
+use common::{extract, run_gitex, Fixture};
+use gitex::ConfigBuilder;
+
+#[test]
+fn tsv_quotes_fields_with_tabs_and_newlines() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Indent with\ttabs",
+        &[(
+            "main.rs",
+            b"fn main() {\n\tprintln!(\"\\\"quoted\\\"\");\n}\n",
+        )],
+    );
+
+    let output = run_gitex(&[
+        "--repository",
+        fixture.path().to_str().unwrap(),
+        "--extensions",
+        "rs",
+        "--size",
+        "1",
+        "--delimiter",
+        r"\t",
+    ]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("commit_hash\tcommit_message\tcommit_changes\n"));
+    assert!(stdout.contains("\t\"Indent with\ttabs\"\t\""));
+
+    let records = extract(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1),
+    );
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b'\t')
+        .from_reader(stdout.as_bytes());
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(&rows[0][1], "Indent with\ttabs");
+    assert_eq!(&rows[0][2], records[0].commit_changes);
+    assert!(rows[0][2].contains("+\tprintln!(\"\\\"quoted\\\"\");\n"));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::Fixture;
+use gitex::ConfigBuilder;
+
+#[test]
+fn dates_are_checked_against_the_days_of_their_month() {
+    let fixture = Fixture::new();
+    let build = |date: &str| {
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("since", date)
+            .build()
+    };
+
+    for date in ["2023-01-31", "2023-04-30", "2024-02-29", "2000-02-29"] {
+        assert!(build(date).is_ok(), "{date}");
+    }
+    for date in [
+        "2023-02-31",
+        "2023-04-31",
+        "2023-02-29",
+        "1900-02-29",
+        "2023-01-00",
+    ] {
+        let Err(error) = build(date) else {
+            panic!("invalid date {date} accepted");
+        };
+        assert!(error
+            .to_string()
+            .contains(&format!("invalid date '{date}'")));
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{extract, Fixture};
+use gitex::ConfigBuilder;
+
+/// Get the commit changes of the last commit of a fixture changing a line in the middle of a
+/// file, with the given number of context lines.
+fn extract_changes(fixture: &Fixture, context_lines: &str) -> String {
+    let records = extract(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("context-lines", context_lines),
+    );
+    records[0].commit_changes.clone()
+}
+
+#[test]
+fn context_lines_set_the_lines_around_changes() {
+    let fixture = Fixture::new();
+    let lines: Vec<String> = (0..9)
+        .map(|i| format!("const N{i}: u32 = {i};\n"))
+        .collect();
+    fixture.commit(
+        "Add the constants",
+        &[("main.rs", lines.concat().as_bytes())],
+    );
+    let mut changed_lines = lines.clone();
+    changed_lines[4] = "const N4: u32 = 40;\n".to_string();
+    fixture.commit(
+        "Change the middle constant",
+        &[("main.rs", changed_lines.concat().as_bytes())],
+    );
+
+    let changes = extract_changes(&fixture, "0");
+    assert!(changes.contains("@@ -5 +5 @@"));
+    assert!(changes.contains("-const N4: u32 = 4;\n+const N4: u32 = 40;\n"));
+    assert!(!changes.contains("\n const N3: u32 = 3;"));
+
+    let changes = extract_changes(&fixture, "3");
+    assert!(changes.contains("@@ -2,7 +2,7 @@"));
+    assert!(changes.contains("\n const N1: u32 = 1;"));
+    assert!(changes.contains("\n const N7: u32 = 7;"));
+    assert!(!changes.contains("\n const N0: u32 = 0;"));
+}
+
+#[test]
+fn renamed_file_is_detected_unless_disabled() {
+    let fixture = Fixture::new();
+    let lines: Vec<String> = (0..9)
+        .map(|i| format!("const N{i}: u32 = {i};\n"))
+        .collect();
+    fixture.commit(
+        "Add the constants",
+        &[("old.rs", lines.concat().as_bytes())],
+    );
+    let mut changed_lines = lines.clone();
+    changed_lines[4] = "const N4: u32 = 40;\n".to_string();
+    fixture.rename(
+        "Rename the constants",
+        "old.rs",
+        "new.rs",
+        changed_lines.concat().as_bytes(),
+    );
+
+    let changes = extract_changes(&fixture, "3");
+    assert!(changes.contains("diff --git a/old.rs b/new.rs\n"));
+    assert!(changes.contains("rename from old.rs\n"));
+    assert!(!changes.contains("\n-const N0: u32 = 0;"));
+
+    let records = extract(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .flag("no-rename-detection"),
+    );
+    let changes = &records[0].commit_changes;
+    assert!(changes.contains("diff --git a/new.rs b/new.rs\n"));
+    assert!(changes.contains("diff --git a/old.rs b/old.rs\n"));
+    assert!(changes.contains("\n-const N0: u32 = 0;"));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{extract, Fixture};
+use gitex::ConfigBuilder;
+
+#[test]
+fn non_utf8_changes_are_skipped_unless_lossy() {
+    let fixture = Fixture::new();
+    fixture.commit("Add the main module", &[("main.py", b"print()\n")]);
+    fixture.commit(
+        "Add the Latin-1 greeting",
+        &[("greeting.py", b"print(\"caf\xe9\")\n")],
+    );
+    let config = || {
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["py"])
+            .size(2)
+    };
+
+    let records = extract(config());
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].commit_message, "Add the main module");
+
+    let records = extract(config().flag("lossy"));
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].commit_message, "Add the Latin-1 greeting");
+    assert!(records[0]
+        .commit_changes
+        .contains("+print(\"caf\u{fffd}\")\n"));
+}
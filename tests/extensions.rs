@@ -0,0 +1,64 @@
+mod common;
+
+use common::{extract, Fixture};
+use gitex::ConfigBuilder;
+
+/// Extract the paths of the files targeted by the given extensions and file names, one record
+/// per file, from commits changing other files as well.
+fn extract_file_paths(fixture: &Fixture, extensions: &[&str], file_names: &str) -> Vec<String> {
+    let mut config = ConfigBuilder::new(fixture.path())
+        .extensions(extensions)
+        .size(10)
+        .flag("per-file")
+        .flag("allow-mixed");
+    if !file_names.is_empty() {
+        config = config.option("include-filename", file_names);
+    }
+    let mut file_paths: Vec<String> = extract(config)
+        .into_iter()
+        .map(|record| record.file_path.unwrap())
+        .collect();
+    file_paths.sort();
+    file_paths
+}
+
+#[test]
+fn extensions_match_case_insensitively_and_compound_suffixes() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Add the documented types",
+        &[
+            ("README.MD", b"# Types\n"),
+            ("types.d.ts", b"export type Id = string;\n"),
+            ("archive.tar.gz", b"archive\n"),
+        ],
+    );
+    fixture.commit(
+        "Add the notes",
+        &[("notes.txt", b"Notes\n"), ("archive.gz", b"archive\n")],
+    );
+
+    assert_eq!(
+        extract_file_paths(&fixture, &["md", "d.ts", "tar.gz"], ""),
+        ["README.MD", "archive.tar.gz", "types.d.ts"]
+    );
+    assert_eq!(extract_file_paths(&fixture, &["ts"], ""), ["types.d.ts"]);
+}
+
+#[test]
+fn include_filename_targets_files_by_their_full_name() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Add the build files",
+        &[
+            ("Makefile", b"all:\n\tcargo build\n"),
+            (".gitignore", b"/target\n"),
+        ],
+    );
+    fixture.commit("Add the license", &[("LICENSE", b"MIT\n")]);
+
+    assert_eq!(
+        extract_file_paths(&fixture, &["rs"], "Makefile,.gitignore"),
+        [".gitignore", "Makefile"]
+    );
+}
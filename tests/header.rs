@@ -0,0 +1,51 @@
+mod common;
+
+use std::fs;
+
+use common::{run_gitex, Fixture};
+use tempfile::TempDir;
+
+/// Run gitex on a fixture without any qualifying commit, and read the output file.
+fn read_empty_output(header_on_empty: &[&str]) -> String {
+    let fixture = Fixture::new();
+    fixture.commit("Add the readme", &[("README.md", b"# Fixture\n")]);
+    let dir = TempDir::new().unwrap();
+    let output_file = dir.path().join("output.csv");
+    let mut args = vec![
+        "--repository",
+        fixture.path().to_str().unwrap(),
+        "--output",
+        output_file.to_str().unwrap(),
+        "--extensions",
+        "rs",
+        "--size",
+        "1",
+    ];
+    args.extend(header_on_empty);
+
+    let output = run_gitex(&args);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    fs::read_to_string(output_file).unwrap_or_default()
+}
+
+#[test]
+fn header_is_written_to_an_empty_dataset_by_default() {
+    assert_eq!(
+        read_empty_output(&[]),
+        "commit_hash,commit_message,commit_changes\n"
+    );
+    assert_eq!(
+        read_empty_output(&["--header-on-empty", "true"]),
+        "commit_hash,commit_message,commit_changes\n"
+    );
+}
+
+#[test]
+fn header_is_not_written_to_an_empty_dataset_without_header_on_empty() {
+    assert_eq!(read_empty_output(&["--header-on-empty", "false"]), "");
+}
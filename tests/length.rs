@@ -0,0 +1,96 @@
+mod common;
+
+use common::{extract, extract_messages, Fixture};
+use gitex::{ConfigBuilder, Extractor};
+
+#[test]
+fn multibyte_message_length_counts_chars() {
+    let fixture = Fixture::new();
+    // 10 characters, but 14 bytes
+    fixture.commit("Réécrit ça", &[("main.rs", b"fn main() {}\n")]);
+    let config = || {
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("message-len-min", "10")
+            .option("message-len-max", "10")
+    };
+
+    assert_eq!(extract_messages(config()), ["Réécrit ça"]);
+    assert!(extract_messages(config().option("message-len-unit", "bytes")).is_empty());
+}
+
+#[test]
+fn changes_length_is_measured_in_the_given_unit() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Add the greeting",
+        &[(
+            "main.rs",
+            "fn main() {\n    println!(\"¡Hola, señor!\");\n}\n".as_bytes(),
+        )],
+    );
+    let config = |unit: &str, min: usize, max: usize| {
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("changes-len-unit", unit)
+            .option("changes-len-min", min.to_string())
+            .option("changes-len-max", max.to_string())
+    };
+    let changes = extract(config("bytes", 1, usize::MAX))
+        .pop()
+        .unwrap()
+        .commit_changes;
+
+    for (unit, len) in [
+        ("bytes", changes.len()),
+        ("chars", changes.chars().count()),
+        ("lines", changes.lines().count()),
+    ] {
+        assert_eq!(extract(config(unit, len, len)).len(), 1, "{unit}");
+        assert!(extract(config(unit, 1, len - 1)).is_empty(), "{unit}");
+        assert!(
+            extract(config(unit, len + 1, usize::MAX)).is_empty(),
+            "{unit}"
+        );
+    }
+    assert!(changes.chars().count() < changes.len());
+}
+
+#[test]
+fn truncated_changes_hold_in_the_maximum_length() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Add the main module",
+        &[(
+            "main.rs",
+            b"fn main() {\n    println!(\"Hello, world!\");\n}\n",
+        )],
+    );
+    let config = |unit: &str, max: usize| {
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("changes-len-unit", unit)
+            .option("changes-len-max", max.to_string())
+            .flag("truncate-changes")
+    };
+
+    for (unit, marker_len) in [("bytes", 15), ("chars", 15), ("lines", 1)] {
+        let changes = extract(config(unit, marker_len))
+            .pop()
+            .unwrap()
+            .commit_changes;
+        assert_eq!(changes, "...[truncated]", "{unit}");
+
+        let config = config(unit, marker_len - 1).build().unwrap();
+        let Err(error) = Extractor::new(config) else {
+            panic!("maximum shorter than the marker accepted in {unit}");
+        };
+        assert_eq!(
+            error.to_string(),
+            format!("--changes-len-max must be at least {marker_len} {unit} with --truncate-changes, the length of the truncation marker")
+        );
+    }
+}
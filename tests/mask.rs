@@ -0,0 +1,51 @@
+mod common;
+
+use common::{extract_messages, Fixture};
+use gitex::ConfigBuilder;
+
+#[test]
+fn default_mask_masks_tickets_but_not_names_with_numbers() {
+    let fixture = Fixture::new();
+    for message in [
+        "Fix UTF-8 handling (#12)",
+        "Hash files with SHA-256 for JIRA-5678",
+        "Parse ISO-8601 dates",
+    ] {
+        fixture.commit(message, &[("main.rs", message.as_bytes())]);
+    }
+
+    let messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(3)
+            .flag("mask-identifiers"),
+    );
+
+    assert_eq!(
+        messages,
+        [
+            "Parse ISO-8601 dates",
+            "Hash files with SHA-256 for <TICKET>",
+            "Fix UTF-8 handling (#<NUM>)",
+        ]
+    );
+}
+
+#[test]
+fn mask_pattern_masks_listed_ticket_keys() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Fix UTF-8 handling for JIRA-5678",
+        &[("main.rs", b"fn main() {}\n")],
+    );
+
+    let messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .flag("mask-identifiers")
+            .option("mask-pattern", r"\b(JIRA|OPS)-[0-9]+\b=<TICKET>"),
+    );
+
+    assert_eq!(messages, ["Fix UTF-8 handling for <TICKET>"]);
+}
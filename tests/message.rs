@@ -0,0 +1,51 @@
+mod common;
+
+use common::{extract_messages, Fixture};
+use gitex::ConfigBuilder;
+
+/// Create a fixture repository with a merge message, a work in progress message and a feature.
+fn create_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    let commits: [(&str, &[u8]); 3] = [
+        ("Merge branch 'main' into feature", b"// merge\n"),
+        ("WIP: try another parser", b"// wip\n"),
+        ("Add the parser", b"// parser\n"),
+    ];
+    for (message, content) in commits {
+        fixture.commit(message, &[("main.rs", content)]);
+    }
+    fixture
+}
+
+#[test]
+fn message_exclude_extends_default_patterns() {
+    let fixture = create_fixture();
+
+    let messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(3)
+            .option("message-exclude", "^WIP"),
+    );
+
+    assert_eq!(messages, ["Add the parser"]);
+}
+
+#[test]
+fn no_default_message_exclude_keeps_merge_messages() {
+    let fixture = create_fixture();
+
+    let mut messages = extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(3)
+            .option("message-exclude", "^WIP")
+            .flag("no-default-message-exclude"),
+    );
+    messages.sort();
+
+    assert_eq!(
+        messages,
+        ["Add the parser", "Merge branch 'main' into feature"]
+    );
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{extract, run_gitex, Fixture};
+use gitex::ConfigBuilder;
+
+#[test]
+fn null_delimited_fields_round_trip() {
+    let fixture = Fixture::new();
+    fixture.commit(
+        "Indent the main function\n\nUse tabs\tinstead of spaces,\nas \"rustfmt\" does.",
+        &[("main.rs", b"fn main() {\n\tprintln!(\"a,b\");\n}\n")],
+    );
+    let repository = fixture.path().to_str().unwrap();
+
+    let output = run_gitex(&[
+        "--repository",
+        repository,
+        "--extensions",
+        "rs",
+        "--size",
+        "1",
+        "--include-body",
+        "--format",
+        "null-delimited",
+    ]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let fields: Vec<&str> = stdout.strip_suffix('\0').unwrap().split('\0').collect();
+    let (header, values) = fields.split_at(4);
+    assert_eq!(
+        header,
+        [
+            "commit_hash",
+            "commit_message",
+            "commit_body",
+            "commit_changes"
+        ]
+    );
+
+    let records = extract(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .flag("include-body"),
+    );
+    let record = &records[0];
+    assert_eq!(
+        values,
+        [
+            record.commit_hash.as_deref().unwrap(),
+            record.commit_message.as_str(),
+            record.commit_body.as_deref().unwrap(),
+            record.commit_changes.as_str(),
+        ]
+    );
+    assert!(values[2].contains("tabs\tinstead of spaces,\nas"));
+    assert!(values[3].contains("+\tprintln!(\"a,b\");\n"));
+}
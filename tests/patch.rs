@@ -0,0 +1,48 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::{extract, Fixture};
+use gitex::ConfigBuilder;
+use tempfile::TempDir;
+
+#[test]
+fn applicable_changes_apply_to_the_parent_commit() {
+    let fixture = Fixture::new();
+    let parent = fixture.commit(
+        "Add the modules",
+        &[("main.rs", b"fn main() {\n    run();\n}\n")],
+    );
+    fixture.commit(
+        "Change the main module",
+        &[
+            ("main.rs", b"fn main() {\n    run();\n    exit();\n}\n"),
+            ("lib.rs", b"pub fn run() {}\n"),
+        ],
+    );
+    let records = extract(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(1)
+            .option("patch-format", "applicable"),
+    );
+
+    let dir = TempDir::new().unwrap();
+    let patch_file = dir.path().join("changes.patch");
+    fs::write(&patch_file, &records[0].commit_changes).unwrap();
+    fixture.checkout_new_branch("parent", parent);
+    let output = Command::new("git")
+        .args(["apply", "--check"])
+        .arg(&patch_file)
+        .current_dir(fixture.path())
+        .output()
+        .expect("failed to run git");
+
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stderr),
+        records[0].commit_changes
+    );
+}
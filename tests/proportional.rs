@@ -0,0 +1,41 @@
+mod common;
+
+use std::fs;
+
+use common::{run_gitex, Fixture};
+use tempfile::TempDir;
+
+#[test]
+fn proportional_counts_each_skip_once() {
+    let fixture = Fixture::new();
+    fixture.commit("Add the main module", &[("main.rs", b"fn main() {}\n")]);
+    fixture.commit("Add the readme", &[("README.md", b"# Fixture\n")]);
+    fixture.commit("Add the script", &[("run.py", b"print()\n")]);
+
+    let dir = TempDir::new().unwrap();
+    let output_file = dir.path().join("output.csv");
+    let stats_file = dir.path().join("stats.json");
+    let output = run_gitex(&[
+        "--repository",
+        fixture.path().to_str().unwrap(),
+        "--output",
+        output_file.to_str().unwrap(),
+        "--extensions",
+        "rs,py",
+        "--size",
+        "2",
+        "--proportional",
+        "--stats-out",
+        stats_file.to_str().unwrap(),
+    ]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stats: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(stats_file).unwrap()).unwrap();
+    assert_eq!(stats["commits_saved"], 2);
+    assert_eq!(stats["commits_skipped"]["no_target"], 1);
+}
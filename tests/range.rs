@@ -0,0 +1,69 @@
+mod common;
+
+use common::{extract_messages, Fixture};
+use gitex::{ConfigBuilder, Extractor};
+
+/// Create a fixture repository whose main and feature branches fork after a base commit.
+/// Return the fixture with the last commits of the main and feature branches.
+fn create_fixture() -> (Fixture, String, String) {
+    let fixture = Fixture::new();
+    let base = fixture.commit("Add the base module", &[("base.rs", b"fn base() {}\n")]);
+    let main = fixture.commit("Add the main module", &[("main.rs", b"fn main() {}\n")]);
+    fixture.checkout_new_branch("feature", base);
+    let feature = fixture.commit(
+        "Add the feature module",
+        &[("feature.rs", b"fn feature() {}\n")],
+    );
+    (fixture, main.to_string(), feature.to_string())
+}
+
+/// Extract the commit messages of a given range of a fixture.
+fn extract_range_messages(fixture: &Fixture, range: &str) -> Vec<String> {
+    extract_messages(
+        ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(10)
+            .option("range", range),
+    )
+}
+
+#[test]
+fn two_dot_range_walks_commits_of_the_end_only() {
+    let (fixture, main, feature) = create_fixture();
+
+    assert_eq!(
+        extract_range_messages(&fixture, &format!("{main}..{feature}")),
+        ["Add the feature module"]
+    );
+}
+
+#[test]
+fn three_dot_range_walks_commits_of_either_side() {
+    let (fixture, main, feature) = create_fixture();
+
+    assert_eq!(
+        extract_range_messages(&fixture, &format!("{main}...{feature}")),
+        ["Add the feature module", "Add the main module"]
+    );
+}
+
+#[test]
+fn invalid_range_fails() {
+    let (fixture, main, _) = create_fixture();
+
+    for range in [main, "unknown..HEAD".to_string()] {
+        let config = ConfigBuilder::new(fixture.path())
+            .extensions(&["rs"])
+            .size(10)
+            .option("range", &range)
+            .build()
+            .unwrap();
+        let error = Extractor::new(config)
+            .and_then(|mut extractor| extractor.extract())
+            .err()
+            .expect("invalid range extracted");
+        assert!(error
+            .to_string()
+            .starts_with(&format!("failed to resolve the range {range}")));
+    }
+}
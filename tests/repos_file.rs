@@ -0,0 +1,60 @@
+mod common;
+
+use std::fs;
+
+use common::{run_gitex, Fixture};
+use tempfile::TempDir;
+
+#[test]
+fn repos_file_sums_stats_and_skips_non_git_paths() {
+    let first = Fixture::new();
+    first.commit("Add the main module", &[("main.rs", b"fn main() {}\n")]);
+    let second = Fixture::new();
+    second.commit("Add the lib module", &[("lib.rs", b"pub fn run() {}\n")]);
+    second.commit("Add the test module", &[("test.rs", b"fn test() {}\n")]);
+    let not_git = TempDir::new().unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let repos_file = dir.path().join("repos.txt");
+    fs::write(
+        &repos_file,
+        format!(
+            "{},first\n{}\n{},second\n",
+            first.path().display(),
+            not_git.path().display(),
+            second.path().display()
+        ),
+    )
+    .unwrap();
+    let output_file = dir.path().join("output.csv");
+    let stats_file = dir.path().join("stats.json");
+
+    let output = run_gitex(&[
+        "--repos-file",
+        repos_file.to_str().unwrap(),
+        "--output",
+        output_file.to_str().unwrap(),
+        "--extensions",
+        "rs",
+        "--size",
+        "10",
+        "--stats-out",
+        stats_file.to_str().unwrap(),
+    ]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("which is not a Git repository"));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("Total commits saved").count(), 1);
+    assert!(stdout.contains("Total commits saved: 3"));
+
+    let stats: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(stats_file).unwrap()).unwrap();
+    assert_eq!(stats["commits_scanned"], 5);
+    assert_eq!(stats["commits_saved"], 3);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::fs;
+
+use common::{run_gitex, Fixture};
+use tempfile::TempDir;
+
+/// Create a fixture repository of 20 commits changing Rust files.
+fn create_fixture() -> Fixture {
+    let fixture = Fixture::new();
+    for i in 0..20 {
+        let content = format!("fn f{i}() {{}}\n");
+        fixture.commit(
+            &format!("Add function number {i}"),
+            &[(&format!("f{i}.rs"), content.as_bytes())],
+        );
+    }
+    fixture
+}
+
+/// Split the commits of a fixture with the given seed options, and read the train and test files.
+fn split(fixture: &Fixture, seed_options: &[&str]) -> (String, String) {
+    let dir = TempDir::new().unwrap();
+    let train_file = dir.path().join("train.csv");
+    let test_file = dir.path().join("test.csv");
+    let mut args = vec![
+        "--repository",
+        fixture.path().to_str().unwrap(),
+        "--output",
+        train_file.to_str().unwrap(),
+        "--test-output",
+        test_file.to_str().unwrap(),
+        "--extensions",
+        "rs",
+        "--size",
+        "20",
+        "--split",
+        "0.5",
+    ];
+    args.extend(seed_options);
+
+    let output = run_gitex(&args);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (
+        fs::read_to_string(train_file).unwrap(),
+        fs::read_to_string(test_file).unwrap(),
+    )
+}
+
+#[test]
+fn split_saves_each_record_to_one_file() {
+    let fixture = create_fixture();
+
+    let (train, test) = split(&fixture, &[]);
+
+    let header = "commit_hash,commit_message,commit_changes\n";
+    assert!(train.starts_with(header));
+    assert!(test.starts_with(header));
+    for i in 0..20 {
+        let message = format!("Add function number {i},");
+        assert!(train.contains(&message) != test.contains(&message));
+    }
+}
+
+#[test]
+fn split_seed_keeps_the_split_when_the_seed_changes() {
+    let fixture = create_fixture();
+
+    assert_eq!(
+        split(&fixture, &["--seed", "1", "--split-seed", "7"]),
+        split(&fixture, &["--seed", "2", "--split-seed", "7"])
+    );
+
+    // The split seed defaults to the seed
+    assert_eq!(
+        split(&fixture, &["--seed", "7"]),
+        split(&fixture, &["--seed", "1", "--split-seed", "7"])
+    );
+    assert_ne!(
+        split(&fixture, &["--seed", "1"]),
+        split(&fixture, &["--seed", "2"])
+    );
+}